@@ -0,0 +1,606 @@
+/*
+* Copyright 2024, Roma Hlushko
+* SPDX-License-Identifier: Apache-2.0
+*/
+
+use crate::diagnostics::{Diagnostic, Diagnostics, Rule, Severity};
+use crate::helm::path::{self, Segment, ValuePath};
+use crate::helm::values::{self, ChartValues};
+use crate::markdown::render::MarkdownRenderer;
+use crate::metadata::chart::ChartMetadata;
+use crate::metadata::modifier::Modifier;
+use crate::metadata::value::ValueMetadata;
+use anyhow::Result;
+use serde_yaml::Value;
+use similar::{DiffTag, TextDiff};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Drift is the difference between the current and the expected markdown file
+pub struct Drift {
+    /// The first line (1-based) that differs in the current file
+    pub line: usize,
+    /// Unified diff between the current and the expected file
+    pub diff: String,
+}
+
+/// Violation is a default value that contradicts the metadata of its param (e.g. a constraint or a type)
+pub struct Violation {
+    pub param: Rc<ValueMetadata>,
+    /// The path of the value (e.g. ports[0].protocol for ports[*].protocol)
+    pub value_path: String,
+    pub reason: String,
+}
+
+/// ValuesChecker verifies that chart values are in sync with their metadata
+pub struct ValuesChecker {}
+
+impl ValuesChecker {
+    pub fn new() -> ValuesChecker {
+        ValuesChecker {}
+    }
+
+    /// Find value paths that have no metadata (@param, @skip or @extra) defined for them.
+    /// Skipped and subtree params cover all their nested values as well.
+    /// Lists are also covered when all values in their items are documented (e.g. via ports[*].name)
+    pub fn find_missing(&self, metadata: &ChartMetadata, values: &ChartValues) -> Vec<String> {
+        let params = metadata.values();
+        let mut missing = Vec::new();
+
+        for path in values.paths() {
+            let documented = params.iter().any(|param| {
+                path.as_str() == param.name()
+                    || ((param.has_skipped() || param.is_subtree()) && path.is_nested(param.path()))
+            });
+
+            if documented {
+                continue;
+            }
+
+            let item_paths = values.list_item_paths(&path);
+
+            // values in list items are leaves unless they are lists with items themselves
+            let item_leaves: Vec<&ValuePath> = item_paths
+                .iter()
+                .filter(|item| !item_paths.iter().any(|other| other.is_nested(item)))
+                .collect();
+
+            let (covered, uncovered): (Vec<&ValuePath>, Vec<&ValuePath>) =
+                item_leaves.into_iter().partition(|item| {
+                    params
+                        .iter()
+                        .any(|param| item.matches(param.path()) || item.is_nested(param.path()))
+                });
+
+            // fields of empty lists can still be documented for the items users add (e.g. tolerations[*].key)
+            let documents_items = params.iter().any(|param| param.path().is_nested(&path));
+
+            if covered.is_empty() && !(item_paths.is_empty() && documents_items) {
+                missing.push(path.to_string());
+
+                continue;
+            }
+
+            missing.extend(uncovered.into_iter().map(|item| item.to_string()));
+        }
+
+        missing
+    }
+
+    /// Find metadata that refers to values that don't exist in the values file (e.g. renamed or removed ones).
+    /// Wildcard params of empty lists are not stale as there are no items to match yet.
+    /// Extra params are not expected to be in the values file, so they are never stale
+    pub fn find_stale(
+        &self,
+        metadata: &ChartMetadata,
+        values: &ChartValues,
+    ) -> Vec<Rc<ValueMetadata>> {
+        let mut paths = values.paths();
+        paths.extend(values.item_paths());
+
+        metadata
+            .values()
+            .iter()
+            .filter(|param| !param.has_extra())
+            .filter(|param| {
+                !list_path(param.name()).is_some_and(|list_path| values.is_empty_list(&list_path))
+                    && !paths
+                        .iter()
+                        .any(|path| path.matches(param.path()) || path.is_nested(param.path()))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Find default values that don't satisfy constraints of their params (e.g. [enum: a|b]).
+    pub fn find_violations(
+        &self,
+        metadata: &ChartMetadata,
+        values: &ChartValues,
+    ) -> Vec<Violation> {
+        let mut paths = values.paths();
+        paths.extend(values.item_paths());
+
+        let mut violations = Vec::new();
+
+        for param in metadata.values() {
+            if param.has_skipped() || param.has_extra() {
+                continue;
+            }
+
+            let constraints: Vec<&Modifier> = param
+                .modifiers()
+                .iter()
+                .filter(|m| m.is_constraint())
+                .collect();
+
+            if constraints.is_empty() {
+                continue;
+            }
+
+            for path in paths.iter().filter(|path| path.matches(param.path())) {
+                let Some(value) = values.get(path.as_str()) else {
+                    continue;
+                };
+
+                for constraint in &constraints {
+                    if let Some(reason) = constraint.violation(&value) {
+                        violations.push(Violation {
+                            param: Rc::clone(param),
+                            value_path: path.to_string(),
+                            reason,
+                        });
+                    }
+                }
+            }
+        }
+
+        violations
+    }
+
+    /// Find params which declared type (e.g. [string]) contradicts their default values
+    pub fn find_type_mismatches(
+        &self,
+        metadata: &ChartMetadata,
+        values: &ChartValues,
+    ) -> Vec<Violation> {
+        let mut mismatches = Vec::new();
+
+        for param in metadata.values() {
+            let Some(declared_type) = param.declared_type() else {
+                continue;
+            };
+
+            let Some(value) = values
+                .get(param.name())
+                .or_else(|| values.subtree(param.name()))
+            else {
+                continue;
+            };
+
+            let actual_type = values::value_type(&value);
+
+            if !is_compatible(declared_type, actual_type) {
+                mismatches.push(Violation {
+                    param: Rc::clone(param),
+                    value_path: param.name().to_string(),
+                    reason: format!(
+                        "the value is declared as {}, but the default is {}",
+                        declared_type, actual_type
+                    ),
+                });
+            }
+        }
+
+        mismatches
+    }
+
+    /// Find required params that have non-empty defaults, so users are never asked to provide them
+    pub fn find_required_defaults(
+        &self,
+        metadata: &ChartMetadata,
+        values: &ChartValues,
+    ) -> Vec<Rc<ValueMetadata>> {
+        metadata
+            .values()
+            .iter()
+            .filter(|param| param.is_required() && !param.has_extra())
+            .filter(|param| {
+                let value = if param.is_subtree() {
+                    values.subtree(param.name())
+                } else {
+                    values.get(param.name())
+                };
+
+                value.is_some_and(|value| !is_empty(&value))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Render the markdown file in memory and compare it to the current one.
+    /// Returns a unified diff between them if the markdown file is outdated
+    pub fn find_drift(
+        &self,
+        markdown_path: &PathBuf,
+        renderer: &MarkdownRenderer,
+        metadata: &ChartMetadata,
+        values: &ChartValues,
+        diagnostics: &Diagnostics,
+    ) -> Result<Option<Drift>> {
+        let Some(expected_content) =
+            renderer.render_content(markdown_path, metadata, values, diagnostics)?
+        else {
+            return Ok(None);
+        };
+
+        let actual_content = fs::read_to_string(markdown_path)?;
+
+        if actual_content == expected_content {
+            return Ok(None);
+        }
+
+        let markdown_name = markdown_path.to_string_lossy();
+        let text_diff = TextDiff::from_lines(&actual_content, &expected_content);
+
+        let line = text_diff
+            .ops()
+            .iter()
+            .find(|op| op.tag() != DiffTag::Equal)
+            .map(|op| op.old_range().start + 1)
+            .unwrap_or(1);
+
+        let diff = text_diff
+            .unified_diff()
+            .header(&markdown_name, &markdown_name)
+            .to_string();
+
+        Ok(Some(Drift { line, diff }))
+    }
+
+    /// Report chart values that have no metadata
+    pub fn check_missing<P: AsRef<Path>>(
+        &self,
+        values_path: P,
+        metadata: &ChartMetadata,
+        values: &ChartValues,
+        severity: Severity,
+        diagnostics: &Diagnostics,
+    ) {
+        for value_path in self.find_missing(metadata, values) {
+            let mut diagnostic = Diagnostic::new(
+                Rule::MissingDoc,
+                severity,
+                &values_path,
+                format!("The {} value is not documented", value_path),
+            );
+
+            if let Some(span) = values.span(&value_path) {
+                diagnostic = diagnostic.with_span(&span);
+            }
+
+            diagnostics.report(diagnostic);
+        }
+    }
+
+    /// Report metadata that refers to values that don't exist
+    pub fn check_stale<P: AsRef<Path>>(
+        &self,
+        values_path: P,
+        metadata: &ChartMetadata,
+        values: &ChartValues,
+        diagnostics: &Diagnostics,
+    ) {
+        for param in self.find_stale(metadata, values) {
+            let mut diagnostic = Diagnostic::error(
+                Rule::StaleParam,
+                &values_path,
+                format!(
+                    "The {} metadata refers to a value that doesn't exist",
+                    param.name()
+                ),
+            );
+
+            if let Some(span) = param.span() {
+                diagnostic = diagnostic.with_span(span);
+            }
+
+            diagnostics.report(diagnostic);
+        }
+    }
+
+    /// Report default values that don't satisfy constraints of their params
+    pub fn check_constraints<P: AsRef<Path>>(
+        &self,
+        values_path: P,
+        metadata: &ChartMetadata,
+        values: &ChartValues,
+        diagnostics: &Diagnostics,
+    ) {
+        for violation in self.find_violations(metadata, values) {
+            let mut diagnostic = Diagnostic::error(
+                Rule::ConstraintViolation,
+                &values_path,
+                format!(
+                    "The {} default doesn't satisfy the {} param: {}",
+                    violation.value_path,
+                    violation.param.name(),
+                    violation.reason
+                ),
+            );
+
+            if let Some(span) = values
+                .span(&violation.value_path)
+                .or_else(|| violation.param.span().cloned())
+            {
+                diagnostic = diagnostic.with_span(&span);
+            }
+
+            diagnostics.report(diagnostic);
+        }
+    }
+
+    /// Report params which declared type contradicts their default values
+    pub fn check_types<P: AsRef<Path>>(
+        &self,
+        values_path: P,
+        metadata: &ChartMetadata,
+        values: &ChartValues,
+        diagnostics: &Diagnostics,
+    ) {
+        for mismatch in self.find_type_mismatches(metadata, values) {
+            let mut diagnostic = Diagnostic::warning(
+                Rule::TypeMismatch,
+                &values_path,
+                format!("The {} param: {}", mismatch.param.name(), mismatch.reason),
+            );
+
+            if let Some(span) = mismatch.param.span() {
+                diagnostic = diagnostic.with_span(span);
+            }
+
+            diagnostics.report(diagnostic);
+        }
+    }
+
+    /// Report required params that have non-empty defaults
+    pub fn check_required<P: AsRef<Path>>(
+        &self,
+        values_path: P,
+        metadata: &ChartMetadata,
+        values: &ChartValues,
+        diagnostics: &Diagnostics,
+    ) {
+        for param in self.find_required_defaults(metadata, values) {
+            let mut diagnostic = Diagnostic::warning(
+                Rule::RequiredDefault,
+                &values_path,
+                format!(
+                    "The {} value is required, but has a non-empty default",
+                    param.name()
+                ),
+            );
+
+            if let Some(span) = values.span(param.name()).or_else(|| param.span().cloned()) {
+                diagnostic = diagnostic.with_span(&span);
+            }
+
+            diagnostics.report(diagnostic);
+        }
+    }
+
+    /// Report the markdown file if it's not in sync with the chart values
+    pub fn check_drift(
+        &self,
+        markdown_path: &PathBuf,
+        renderer: &MarkdownRenderer,
+        metadata: &ChartMetadata,
+        values: &ChartValues,
+        diagnostics: &Diagnostics,
+    ) -> Result<()> {
+        if let Some(drift) =
+            self.find_drift(markdown_path, renderer, metadata, values, diagnostics)?
+        {
+            diagnostics.report(
+                Diagnostic::error(
+                    Rule::ReadmeDrift,
+                    markdown_path,
+                    format!(
+                        "{} is outdated. Run `chartpedia gen` to update it",
+                        markdown_path.to_string_lossy()
+                    ),
+                )
+                .with_line(drift.line)
+                .with_details(drift.diff),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Check if the default value of the given type can have the declared one.
+/// Nulls fit any type and unknown types (e.g. helm-docs tpl) are not checked
+fn is_compatible(declared_type: &str, actual_type: &str) -> bool {
+    const KNOWN_TYPES: [&str; 6] = ["string", "int", "float", "bool", "list", "map"];
+
+    declared_type == actual_type
+        || actual_type == "null"
+        || (declared_type == "float" && actual_type == "int")
+        || !KNOWN_TYPES.contains(&declared_type)
+}
+
+/// Check if the value is left for users to provide (e.g. null, "" or [])
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(value) => value.is_empty(),
+        Value::Sequence(items) => items.is_empty(),
+        Value::Mapping(map) => map.is_empty(),
+        Value::Tagged(tagged) => is_empty(&tagged.value),
+        Value::Bool(_) | Value::Number(_) => false,
+    }
+}
+
+/// Path of the list the wildcard param refers to (e.g. ports for ports[*].name)
+fn list_path(param_path: &str) -> Option<String> {
+    let segments = path::parse(param_path).ok()?;
+    let wildcard_idx = segments.iter().position(|s| *s == Segment::Wildcard)?;
+
+    Some(path::format(&segments[..wildcard_idx]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_yaml::Value;
+
+    fn values(paths: &[(&str, Value)], item_paths: &[&str]) -> ChartValues {
+        let values = ChartValues::new();
+
+        for (path, value) in paths {
+            values.insert(path.to_string(), value.clone());
+        }
+
+        for path in item_paths {
+            values.insert_item(path.to_string(), Value::Null);
+        }
+
+        values
+    }
+
+    fn metadata(params: Vec<ValueMetadata>) -> ChartMetadata {
+        let mut metadata = ChartMetadata::new();
+
+        for param in params {
+            metadata.add_value(Rc::new(param));
+        }
+
+        metadata
+    }
+
+    fn param(name: &str) -> ValueMetadata {
+        ValueMetadata::new(name.to_string(), vec![], None)
+    }
+
+    fn skipped(name: &str) -> ValueMetadata {
+        let mut param = param(name);
+        param.skip();
+
+        param
+    }
+
+    fn list(len: usize) -> Value {
+        Value::Sequence(vec![Value::Null; len])
+    }
+
+    #[test]
+    fn skipped_params_cover_nested_values() {
+        let values = values(
+            &[
+                ("internal.token", Value::Null),
+                ("internal.nested.flag", Value::Null),
+                ("replicas", Value::Null),
+                ("image.tag", Value::Null),
+            ],
+            &[],
+        );
+        let metadata = metadata(vec![skipped("internal"), param("replicas")]);
+
+        assert_eq!(
+            ValuesChecker::new().find_missing(&metadata, &values),
+            vec!["image.tag"]
+        );
+    }
+
+    #[test]
+    fn skipped_params_cover_only_their_own_keys() {
+        let values = values(
+            &[("image.tag", Value::Null), ("imagePullPolicy", Value::Null)],
+            &[],
+        );
+        let metadata = metadata(vec![skipped("image")]);
+
+        assert_eq!(
+            ValuesChecker::new().find_missing(&metadata, &values),
+            vec!["imagePullPolicy"]
+        );
+    }
+
+    #[test]
+    fn list_items_are_covered_by_wildcard_params() {
+        let values = values(
+            &[("ports", list(2)), ("hosts", list(1)), ("args", list(1))],
+            &[
+                "ports[0].name",
+                "ports[0].port",
+                "ports[1].name",
+                "ports[1].port",
+                "hosts[0].host",
+                "hosts[0].port",
+                "args[0]",
+            ],
+        );
+        let metadata = metadata(vec![
+            param("ports[*].name"),
+            param("hosts[*].host"),
+            param("hosts[*].port"),
+        ]);
+
+        assert_eq!(
+            ValuesChecker::new().find_missing(&metadata, &values),
+            vec!["ports[0].port", "ports[1].port", "args"]
+        );
+    }
+
+    #[test]
+    fn empty_lists_are_covered_by_their_item_params() {
+        let values = values(&[("tolerations", list(0)), ("affinity", list(0))], &[]);
+        let metadata = metadata(vec![param("tolerations[*].key")]);
+
+        assert_eq!(
+            ValuesChecker::new().find_missing(&metadata, &values),
+            vec!["affinity"]
+        );
+    }
+
+    #[test]
+    fn wildcard_params_of_lists_with_items_must_match() {
+        let values = values(
+            &[("ports", list(0)), ("hosts", list(1))],
+            &["hosts[0].host"],
+        );
+        let metadata = metadata(vec![
+            param("ports[*].name"),
+            param("hosts[*].host"),
+            param("hosts[*].port"),
+        ]);
+
+        let stale: Vec<String> = ValuesChecker::new()
+            .find_stale(&metadata, &values)
+            .iter()
+            .map(|param| param.name().to_string())
+            .collect();
+
+        assert_eq!(stale, vec!["hosts[*].port"]);
+    }
+
+    #[test]
+    fn declared_types_must_match_defaults() {
+        let values = values(&[("replicas", 3.into()), ("image.tag", "1.0".into())], &[]);
+        let mut replicas = param("replicas");
+        let mut tag = param("image.tag");
+
+        replicas.set_param_type("string".to_string());
+        tag.set_param_type("string".to_string());
+
+        let mismatches: Vec<String> = ValuesChecker::new()
+            .find_type_mismatches(&metadata(vec![replicas, tag]), &values)
+            .iter()
+            .map(|mismatch| mismatch.value_path.clone())
+            .collect();
+
+        assert_eq!(mismatches, vec!["replicas"]);
+    }
+}
@@ -2,6 +2,8 @@
 * Copyright 2024, Roma Hlushko
 * SPDX-License-Identifier: Apache-2.0
 */
+use crate::config::CommentDialect;
+use crate::diagnostics::report::Format;
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
@@ -19,14 +21,20 @@ const BANNER: &str = r"
 Basic Usage:
     chartpedia gen
     chartpedia gen -m README.md -v values.yaml
+    chartpedia schema -v values.yaml
+    chartpedia check --no-missing -v values.yaml
 
 Advanced Usage:
     chartpedia --config chartpedia.yaml gen -m README.md -v values.yaml
     chartpedia --debug gen -m README.md -v values.yaml
+    chartpedia check --no-missing --format sarif
+    chartpedia migrate --from helm-docs --to bitnami values.yaml
+    chartpedia fix --prune --dry-run
+    chartpedia fmt --check -v values.yaml
 ";
 
 #[derive(Parser)]
-#[command(author, author, version)]
+#[command(author, version)]
 #[command(about = BANNER)]
 pub struct Cli {
     /// Debug
@@ -69,6 +77,15 @@ pub enum Commands {
         #[arg(short, long, default_value="README.md", value_parser=validate_file_exists)]
         markdown: PathBuf,
     },
+    /// Generate JSON schema of chart values (values.schema.json)
+    Schema {
+        /// Path to a chart values file
+        #[arg(short, long, default_value="values.yaml", value_parser=validate_file_exists)]
+        values: PathBuf,
+        /// Path to the schema file (if empty, values.schema.json is written next to the values file)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
     /// Check generated files based on values.
     Check {
         /// Path to a chart values file
@@ -78,7 +95,46 @@ pub enum Commands {
         #[arg(short, long, default_value="README.md", value_parser=validate_file_exists)]
         markdown: PathBuf,
         /// Fail if there are any undocumented chart values
-        #[arg(short, long, action, default_value = "true")]
+        #[arg(short, long, action = clap::ArgAction::SetTrue)]
         no_missing: bool,
+        /// Output format of the found problems
+        #[arg(short, long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    /// Add param stubs for undocumented values
+    Fix {
+        /// Path to a chart values file
+        #[arg(short, long, default_value="values.yaml", value_parser=validate_file_exists)]
+        values: PathBuf,
+        /// Remove metadata of values that don't exist anymore
+        #[arg(short, long, action = clap::ArgAction::SetTrue)]
+        prune: bool,
+        /// Print the diff instead of changing the values file
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        dry_run: bool,
+    },
+    /// Normalize values metadata comments
+    Fmt {
+        /// Path to a chart values file
+        #[arg(short, long, default_value="values.yaml", value_parser=validate_file_exists)]
+        values: PathBuf,
+        /// Fail if the metadata is not formatted instead of changing the values file
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        check: bool,
+    },
+    /// Rewrite values metadata comments from one dialect (or tag names) to another
+    Migrate {
+        /// Path to a chart values file
+        #[arg(default_value="values.yaml", value_parser=validate_file_exists)]
+        values: PathBuf,
+        /// The dialect the metadata is written in now (if empty, the one from --from-config is used)
+        #[arg(long, value_enum)]
+        from: Option<CommentDialect>,
+        /// The dialect to rewrite the metadata to (if empty, the one from the config is used)
+        #[arg(long, value_enum)]
+        to: Option<CommentDialect>,
+        /// Config the metadata is written with now, e.g. before the tags were renamed (if empty, the default config is used)
+        #[arg(long, value_parser=validate_file_exists)]
+        from_config: Option<PathBuf>,
     },
 }
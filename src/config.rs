@@ -10,20 +10,41 @@ use std::io::Read;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
+/// Defines how the metadata is written in the values file comments
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum CommentDialect {
+    /// The Bitnami Readme Generator tags (e.g. ## @param image.tag Image tag)
+    #[default]
+    Bitnami,
+    /// helm-docs annotations (e.g. # -- Image tag)
+    HelmDocs,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CommentsConfig {
     pub format: String,
+    pub dialect: CommentDialect,
+    /// Append plain comment lines that follow a @param to its description
+    /// (disable for strict compatibility with the Bitnami Readme Generator).
+    /// helm-docs descriptions always continue on the following comment lines
+    #[serde(rename = "multilineDescriptions")]
+    pub multiline_descriptions: bool,
 }
 
 impl Default for CommentsConfig {
     fn default() -> Self {
         CommentsConfig {
             format: "##".to_string(),
+            dialect: CommentDialect::default(),
+            multiline_descriptions: true,
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TagsConfig {
     pub param: String,
     pub section: String,
@@ -33,6 +54,7 @@ pub struct TagsConfig {
     pub description_end: String,
     pub skip: String,
     pub extra: String,
+    pub required: String,
 }
 
 impl Default for TagsConfig {
@@ -44,17 +66,25 @@ impl Default for TagsConfig {
             description_end: "@descriptionEnd".to_string(),
             skip: "@skip".to_string(),
             extra: "@extra".to_string(),
+            required: "@required".to_string(),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ModifiersConfig {
     pub array: String,
     pub object: String,
     pub string: String,
     pub nullable: String,
     pub default: String,
+    pub subtree: String,
+    pub required: String,
+    pub r#enum: String,
+    pub min: String,
+    pub max: String,
+    pub pattern: String,
 }
 
 impl Default for ModifiersConfig {
@@ -65,6 +95,12 @@ impl Default for ModifiersConfig {
             string: "string".to_string(),
             nullable: "nullable".to_string(),
             default: "default".to_string(),
+            subtree: "subtree".to_string(),
+            required: "required".to_string(),
+            r#enum: "enum".to_string(),
+            min: "min".to_string(),
+            max: "max".to_string(),
+            pattern: "pattern".to_string(),
         }
     }
 }
@@ -83,12 +119,53 @@ impl Default for RegexpConfig {
     }
 }
 
+/// Defines how the parameters region is found in the markdown file
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderMode {
+    /// Replace everything under the parameters section heading up to the next heading of the same level
+    #[default]
+    Heading,
+    /// Replace only the content between the start and end marker comments
+    Markers,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarkersConfig {
+    pub start: String,
+    pub end: String,
+}
+
+impl Default for MarkersConfig {
+    fn default() -> Self {
+        MarkersConfig {
+            start: "chartpedia:start".to_string(),
+            end: "chartpedia:end".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarkdownConfig {
+    pub mode: RenderMode,
+    pub markers: MarkersConfig,
+    /// Path to a custom template for the parameters region (the built-in template is used if empty)
+    pub template: Option<PathBuf>,
+    /// Add the column with value types to parameter tables
+    #[serde(rename = "typeColumn")]
+    pub type_column: bool,
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub comments: CommentsConfig,
     pub tags: TagsConfig,
-    pub modifiers: TagsConfig,
+    pub modifiers: ModifiersConfig,
     pub regexp: RegexpConfig,
+    pub markdown: MarkdownConfig,
 }
 
 #[derive(Error, Debug)]
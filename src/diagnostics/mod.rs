@@ -0,0 +1,249 @@
+/*
+* Copyright 2024, Roma Hlushko
+* SPDX-License-Identifier: Apache-2.0
+*/
+use crate::span::Span;
+use serde::Serialize;
+use std::cell::RefCell;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+pub mod report;
+
+/// Rule identifies the kind of problem found in chart files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    ParseError,
+    MissingDoc,
+    StaleParam,
+    DuplicateParam,
+    ReadmeDrift,
+    ParamsSectionNotFound,
+    UnbalancedMarkers,
+    OrphanDescription,
+    NestedDescription,
+    UnterminatedDescription,
+    MalformedModifiers,
+    InvalidPath,
+    UnresolvedName,
+    LossyMigration,
+    UnknownModifier,
+    ConstraintViolation,
+    RequiredDefault,
+    TypeMismatch,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 18] = [
+        Rule::ParseError,
+        Rule::MissingDoc,
+        Rule::StaleParam,
+        Rule::DuplicateParam,
+        Rule::ReadmeDrift,
+        Rule::ParamsSectionNotFound,
+        Rule::UnbalancedMarkers,
+        Rule::OrphanDescription,
+        Rule::NestedDescription,
+        Rule::UnterminatedDescription,
+        Rule::MalformedModifiers,
+        Rule::InvalidPath,
+        Rule::UnresolvedName,
+        Rule::LossyMigration,
+        Rule::UnknownModifier,
+        Rule::ConstraintViolation,
+        Rule::RequiredDefault,
+        Rule::TypeMismatch,
+    ];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            Rule::ParseError => "parse-error",
+            Rule::MissingDoc => "missing-doc",
+            Rule::StaleParam => "stale-param",
+            Rule::DuplicateParam => "duplicate-param",
+            Rule::ReadmeDrift => "readme-drift",
+            Rule::ParamsSectionNotFound => "params-section-not-found",
+            Rule::UnbalancedMarkers => "unbalanced-markers",
+            Rule::OrphanDescription => "orphan-description",
+            Rule::NestedDescription => "nested-description",
+            Rule::UnterminatedDescription => "unterminated-description",
+            Rule::MalformedModifiers => "malformed-modifiers",
+            Rule::InvalidPath => "invalid-path",
+            Rule::UnresolvedName => "unresolved-name",
+            Rule::LossyMigration => "lossy-migration",
+            Rule::UnknownModifier => "unknown-modifier",
+            Rule::ConstraintViolation => "constraint-violation",
+            Rule::RequiredDefault => "required-default",
+            Rule::TypeMismatch => "type-mismatch",
+        }
+    }
+
+    pub fn descr(&self) -> &'static str {
+        match self {
+            Rule::ParseError => "The file could not be parsed",
+            Rule::MissingDoc => "The chart value has no metadata",
+            Rule::StaleParam => "The metadata refers to a value that doesn't exist",
+            Rule::DuplicateParam => "The chart value is documented more than once",
+            Rule::ReadmeDrift => "The markdown file is not in sync with the chart values",
+            Rule::ParamsSectionNotFound => "The markdown file has no place for the parameters",
+            Rule::UnbalancedMarkers => "The markdown file has unbalanced marker comments",
+            Rule::OrphanDescription => "The description is not attached to any section",
+            Rule::NestedDescription => "The description is started inside of another description",
+            Rule::UnterminatedDescription => "The description is never ended",
+            Rule::MalformedModifiers => "The param modifiers are malformed",
+            Rule::InvalidPath => "The value path can't be parsed",
+            Rule::UnresolvedName => "The param name can't be inferred from the values file",
+            Rule::LossyMigration => "The metadata can't be fully expressed in the target dialect",
+            Rule::UnknownModifier => "The param has a modifier that is not configured",
+            Rule::ConstraintViolation => "The default value doesn't satisfy the param constraints",
+            Rule::RequiredDefault => "The value must be provided by the user, but has a default",
+            Rule::TypeMismatch => "The declared type contradicts the default value",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// Diagnostic is a problem found in one of the chart files
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub file: PathBuf,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub end_line: Option<usize>,
+    pub end_column: Option<usize>,
+    pub message: String,
+    /// Additional multiline context (e.g. a diff)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new<P: AsRef<Path>>(rule: Rule, severity: Severity, file: P, message: String) -> Self {
+        Diagnostic {
+            rule,
+            severity,
+            file: file.as_ref().to_path_buf(),
+            line: None,
+            column: None,
+            end_line: None,
+            end_column: None,
+            message,
+            details: None,
+        }
+    }
+
+    pub fn error<P: AsRef<Path>>(rule: Rule, file: P, message: String) -> Self {
+        Diagnostic::new(rule, Severity::Error, file, message)
+    }
+
+    pub fn warning<P: AsRef<Path>>(rule: Rule, file: P, message: String) -> Self {
+        Diagnostic::new(rule, Severity::Warning, file, message)
+    }
+
+    pub fn with_line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+
+    pub fn with_span(mut self, span: &Span) -> Self {
+        self.line = Some(span.start_line);
+        self.column = Some(span.start_column);
+        self.end_line = Some(span.end_line);
+        self.end_column = Some(span.end_column);
+        self
+    }
+
+    pub fn with_details(mut self, details: String) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+/// Diagnostics collects problems reported by parsers, renderers and checkers
+pub struct Diagnostics {
+    items: RefCell<Vec<Diagnostic>>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Diagnostics {
+        Diagnostics {
+            items: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn report(&self, diagnostic: Diagnostic) {
+        self.items.borrow_mut().push(diagnostic)
+    }
+
+    /// Report the failure that stops processing of the file.
+    /// The returned error tells that the failure doesn't need to be logged once again
+    pub fn report_failure(&self, diagnostic: Diagnostic) -> ReportedError {
+        let err = ReportedError::new(diagnostic.message.clone());
+
+        self.report(diagnostic);
+
+        err
+    }
+
+    pub fn items(&self) -> Vec<Diagnostic> {
+        self.items.borrow().clone()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.items
+            .borrow()
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+}
+
+/// ReportedError is a failure that is already reported as a diagnostic
+#[derive(Error, Debug)]
+pub struct ReportedError {
+    message: String,
+}
+
+impl fmt::Display for ReportedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl ReportedError {
+    pub fn new(msg: String) -> Self {
+        ReportedError { message: msg }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reported_failures_are_told_apart() {
+        let diagnostics = Diagnostics::new();
+
+        let reported: anyhow::Error = diagnostics
+            .report_failure(Diagnostic::error(
+                Rule::ParseError,
+                "values.yaml",
+                "Failed to parse the values file".to_string(),
+            ))
+            .into();
+        let unreported = anyhow::anyhow!("Failed to render the template");
+
+        assert!(diagnostics.has_errors());
+        assert!(reported.downcast_ref::<ReportedError>().is_some());
+        assert!(unreported.downcast_ref::<ReportedError>().is_none());
+    }
+}
@@ -0,0 +1,287 @@
+/*
+* Copyright 2024, Roma Hlushko
+* SPDX-License-Identifier: Apache-2.0
+*/
+use crate::diagnostics::{Diagnostic, Diagnostics, Rule, Severity};
+use anyhow::Result;
+use clap::ValueEnum;
+use serde_json::json;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+const TOOL_NAME: &str = "chartpedia";
+const TOOL_URI: &str = "https://github.com/roma-glushko/chartpedia";
+
+/// Format defines how diagnostics are printed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Human-readable log messages
+    #[default]
+    Text,
+    /// JSON array of diagnostics
+    Json,
+    /// SARIF 2.1.0 log (e.g. for GitHub code scanning)
+    Sarif,
+    /// JUnit XML report
+    Junit,
+    /// GitHub Actions workflow commands
+    Github,
+}
+
+/// DiagnosticsReporter prints collected diagnostics in the given format
+pub struct DiagnosticsReporter {
+    format: Format,
+}
+
+impl DiagnosticsReporter {
+    pub fn new(format: Format) -> DiagnosticsReporter {
+        DiagnosticsReporter { format }
+    }
+
+    pub fn report(&self, diagnostics: &Diagnostics) -> Result<()> {
+        let diagnostics = diagnostics.items();
+
+        match self.format {
+            Format::Text => self.report_text(&diagnostics),
+            Format::Json => println!("{}", serde_json::to_string_pretty(&diagnostics)?),
+            Format::Sarif => println!(
+                "{}",
+                serde_json::to_string_pretty(&self.to_sarif(&diagnostics))?
+            ),
+            Format::Junit => print!("{}", self.to_junit(&diagnostics)),
+            Format::Github => print!("{}", self.to_github(&diagnostics)),
+        }
+
+        Ok(())
+    }
+
+    fn report_text(&self, diagnostics: &[Diagnostic]) {
+        for diagnostic in diagnostics {
+            let location = match (diagnostic.line, diagnostic.column) {
+                (Some(line), Some(column)) => {
+                    format!("{}:{}:{}", diagnostic.file.to_string_lossy(), line, column)
+                }
+                (Some(line), None) => format!("{}:{}", diagnostic.file.to_string_lossy(), line),
+                _ => diagnostic.file.to_string_lossy().to_string(),
+            };
+
+            match diagnostic.severity {
+                Severity::Error => log::error!(
+                    "{}: {} [{}]",
+                    location,
+                    diagnostic.message,
+                    diagnostic.rule.id()
+                ),
+                Severity::Warning => log::warn!(
+                    "{}: {} [{}]",
+                    location,
+                    diagnostic.message,
+                    diagnostic.rule.id()
+                ),
+            }
+
+            if let Some(details) = &diagnostic.details {
+                println!("{}", details);
+            }
+        }
+    }
+
+    fn to_sarif(&self, diagnostics: &[Diagnostic]) -> serde_json::Value {
+        let rules: Vec<_> = Rule::ALL
+            .iter()
+            .map(|rule| {
+                json!({
+                    "id": rule.id(),
+                    "shortDescription": { "text": rule.descr() },
+                })
+            })
+            .collect();
+
+        let results: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let mut region = serde_json::Map::new();
+
+                if let Some(line) = diagnostic.line {
+                    region.insert("startLine".to_string(), json!(line));
+                }
+
+                if let Some(column) = diagnostic.column {
+                    region.insert("startColumn".to_string(), json!(column));
+                }
+
+                if let Some(end_line) = diagnostic.end_line {
+                    region.insert("endLine".to_string(), json!(end_line));
+                }
+
+                if let Some(end_column) = diagnostic.end_column {
+                    region.insert("endColumn".to_string(), json!(end_column));
+                }
+
+                json!({
+                    "ruleId": diagnostic.rule.id(),
+                    "level": match diagnostic.severity {
+                        Severity::Error => "error",
+                        Severity::Warning => "warning",
+                    },
+                    "message": { "text": diagnostic.message },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": diagnostic.file.to_string_lossy() },
+                            "region": region,
+                        }
+                    }],
+                })
+            })
+            .collect();
+
+        json!({
+            "$schema": SARIF_SCHEMA,
+            "version": SARIF_VERSION,
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": TOOL_NAME,
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "informationUri": TOOL_URI,
+                        "rules": rules,
+                    }
+                },
+                "results": results,
+            }],
+        })
+    }
+
+    fn to_junit(&self, diagnostics: &[Diagnostic]) -> String {
+        let failures = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .count();
+
+        let mut test_cases = Vec::with_capacity(diagnostics.len());
+
+        for diagnostic in diagnostics {
+            let name = match diagnostic.line {
+                Some(line) => format!("{}:{}", diagnostic.file.to_string_lossy(), line),
+                None => diagnostic.file.to_string_lossy().to_string(),
+            };
+
+            let tag = match diagnostic.severity {
+                Severity::Error => "failure",
+                // warnings don't fail the build, so they are reported as test output
+                Severity::Warning => "system-out",
+            };
+
+            let body = match &diagnostic.details {
+                Some(details) => format!("{}\n{}", diagnostic.message, details),
+                None => diagnostic.message.clone(),
+            };
+
+            let attrs = match diagnostic.severity {
+                Severity::Error => format!(
+                    " message=\"{}\" type=\"{}\"",
+                    escape_xml(&diagnostic.message),
+                    diagnostic.rule.id()
+                ),
+                Severity::Warning => "".to_string(),
+            };
+
+            test_cases.push(format!(
+                "    <testcase classname=\"{}.{}\" name=\"{}\">\n      <{}{}>{}</{}>\n    </testcase>\n",
+                TOOL_NAME,
+                diagnostic.rule.id(),
+                escape_xml(&name),
+                tag,
+                attrs,
+                escape_xml(&body),
+                tag,
+            ));
+        }
+
+        if test_cases.is_empty() {
+            test_cases.push(format!(
+                "    <testcase classname=\"{}\" name=\"check\"/>\n",
+                TOOL_NAME
+            ));
+        }
+
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+            <testsuites>\n  \
+            <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">\n{}  \
+            </testsuite>\n\
+            </testsuites>\n",
+            TOOL_NAME,
+            test_cases.len(),
+            failures,
+            test_cases.concat(),
+        )
+    }
+
+    fn to_github(&self, diagnostics: &[Diagnostic]) -> String {
+        diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let command = match diagnostic.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                };
+
+                let mut props = vec![format!(
+                    "file={}",
+                    escape_github_property(&diagnostic.file.to_string_lossy())
+                )];
+
+                if let Some(line) = diagnostic.line {
+                    props.push(format!("line={}", line));
+                }
+
+                if let Some(column) = diagnostic.column {
+                    props.push(format!("col={}", column));
+                }
+
+                if let Some(end_line) = diagnostic.end_line {
+                    props.push(format!("endLine={}", end_line));
+                }
+
+                if let Some(end_column) = diagnostic.end_column {
+                    props.push(format!("endColumn={}", end_column));
+                }
+
+                props.push(format!("title={}", diagnostic.rule.id()));
+
+                let message = match &diagnostic.details {
+                    Some(details) => format!("{}\n{}", diagnostic.message, details),
+                    None => diagnostic.message.clone(),
+                };
+
+                format!(
+                    "::{} {}::{}\n",
+                    command,
+                    props.join(","),
+                    escape_github_data(&message)
+                )
+            })
+            .collect()
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn escape_github_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_github_property(text: &str) -> String {
+    escape_github_data(text)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
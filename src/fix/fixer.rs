@@ -0,0 +1,186 @@
+/*
+* Copyright 2024, Roma Hlushko
+* SPDX-License-Identifier: Apache-2.0
+*/
+
+use crate::check::checker::ValuesChecker;
+use crate::config::Config;
+use crate::helm::document::ValuesDocument;
+use crate::helm::path;
+use crate::helm::values::ChartValues;
+use crate::metadata::chart::ChartMetadata;
+use crate::metadata::writer::{MetadataWriter, ParamComment, ParamKind};
+use similar::TextDiff;
+
+/// The description of generated param stubs
+pub const STUB_DESCRIPTION: &str = "TODO";
+
+/// Fixer brings values metadata in sync with chart values
+pub struct Fixer {
+    checker: ValuesChecker,
+    writer: MetadataWriter,
+}
+
+impl Fixer {
+    pub fn new(config: &Config) -> Fixer {
+        Fixer {
+            checker: ValuesChecker::new(),
+            writer: MetadataWriter::new(config),
+        }
+    }
+
+    /// Insert param stubs above undocumented values.
+    /// Returns the number of inserted stubs
+    pub fn add_stubs(
+        &self,
+        document: &mut ValuesDocument,
+        metadata: &ChartMetadata,
+        values: &ChartValues,
+    ) -> usize {
+        let mut added = 0;
+
+        for value_path in self.checker.find_missing(metadata, values) {
+            let Some(key_line) = self.find_key_line(document, &value_path) else {
+                log::warn!(
+                    "The {} value key is not found in the values file",
+                    value_path
+                );
+
+                continue;
+            };
+
+            let stub = ParamComment {
+                kind: ParamKind::Param,
+                inferred: document.key_line(&value_path) == Some(key_line),
+                name: value_path,
+                modifiers: vec![],
+                descr: STUB_DESCRIPTION.to_string(),
+                continuation: vec![],
+                section: None,
+            };
+
+            let indent = document.indent(key_line).to_string();
+
+            if let Some(lines) = self.writer.param(&indent, &stub) {
+                document.insert_before(key_line, lines);
+                added += 1;
+            }
+        }
+
+        added
+    }
+
+    /// Remove metadata of values that don't exist anymore.
+    /// Returns the number of removed params
+    pub fn prune(
+        &self,
+        document: &mut ValuesDocument,
+        metadata: &ChartMetadata,
+        values: &ChartValues,
+    ) -> usize {
+        let stale = self.checker.find_stale(metadata, values);
+
+        for param in &stale {
+            document.replace_param(param, vec![]);
+        }
+
+        stale.len()
+    }
+
+    /// Find the line of the value key or its closest parent (e.g. for values in flow mappings)
+    fn find_key_line(&self, document: &ValuesDocument, value_path: &str) -> Option<usize> {
+        let segments = path::parse(value_path).ok()?;
+
+        (1..=segments.len())
+            .rev()
+            .find_map(|len| document.key_line(&path::format(&segments[..len])))
+    }
+}
+
+/// Unified diff between the current and the fixed values file
+pub fn diff(values_name: &str, current: &str, fixed: &str) -> String {
+    TextDiff::from_lines(current, fixed)
+        .unified_diff()
+        .header(values_name, values_name)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::parse_chart;
+
+    /// Run the fixer the way `fix --prune` does and return the fixed content with the edit counts
+    fn fix(content: &str) -> (String, usize, usize) {
+        let config = Config::default();
+        let mut chart = parse_chart(content, &config);
+        let fixer = Fixer::new(&config);
+
+        let pruned = fixer.prune(&mut chart.document, &chart.metadata, &chart.values);
+        let added = fixer.add_stubs(&mut chart.document, &chart.metadata, &chart.values);
+
+        (chart.document.render(), added, pruned)
+    }
+
+    #[test]
+    fn stubs_are_inserted_above_keys_with_their_indent() {
+        let (fixed, added, _) = fix("replicas: 1\nimage:\n  tag: v1\n");
+
+        assert_eq!(added, 2);
+        assert_eq!(
+            fixed,
+            "\
+## @param replicas TODO
+replicas: 1
+image:
+  ## @param image.tag TODO
+  tag: v1
+"
+        );
+    }
+
+    #[test]
+    fn stubs_of_values_in_flow_mappings_go_above_the_parent_key() {
+        let (fixed, added, _) = fix("image: {tag: v1}\n");
+
+        assert_eq!(added, 1);
+        assert_eq!(fixed, "## @param image.tag TODO\nimage: {tag: v1}\n");
+    }
+
+    #[test]
+    fn prune_removes_stale_params() {
+        let (fixed, added, pruned) = fix("\
+## @param replicas Replica count
+## @param image.tag Image tag
+replicas: 1
+");
+
+        assert_eq!((added, pruned), (0, 1));
+        assert_eq!(fixed, "## @param replicas Replica count\nreplicas: 1\n");
+    }
+
+    #[test]
+    fn second_run_makes_no_edits() {
+        let (fixed, _, _) = fix("\
+## @param removed Gone
+replicas: 1
+image:
+  tag: v1
+ports:
+  - name: http
+");
+        let config = Config::default();
+        let mut chart = parse_chart(&fixed, &config);
+        let fixer = Fixer::new(&config);
+
+        assert_eq!(
+            fixer.prune(&mut chart.document, &chart.metadata, &chart.values),
+            0
+        );
+        assert_eq!(
+            fixer.add_stubs(&mut chart.document, &chart.metadata, &chart.values),
+            0
+        );
+        assert!(!chart.document.has_edits());
+    }
+}
@@ -0,0 +1,359 @@
+/*
+* Copyright 2024, Roma Hlushko
+* SPDX-License-Identifier: Apache-2.0
+*/
+
+use crate::config::{CommentDialect, Config, ModifiersConfig};
+use crate::helm::document::ValuesDocument;
+use crate::helm::locator::LineToken;
+use crate::helm::path;
+use crate::metadata::chart::ChartMetadata;
+use crate::metadata::modifier::Modifier;
+use crate::metadata::parser::INFERRED_NAME;
+use crate::metadata::value::ValueMetadata;
+use crate::metadata::writer::{MetadataWriter, ParamComment};
+use std::collections::HashSet;
+use std::rc::Rc;
+
+/// Formatter brings metadata comments to the canonical form of the configured dialect
+pub struct Formatter {
+    modifiers: ModifiersConfig,
+    writer: MetadataWriter,
+}
+
+impl Formatter {
+    pub fn new(config: &Config) -> Formatter {
+        Formatter {
+            modifiers: config.modifiers.clone(),
+            writer: MetadataWriter::new(config),
+        }
+    }
+
+    /// Rewrite metadata comments of the values document in the canonical form.
+    /// Everything else in the document is kept as is
+    pub fn format(&self, document: &mut ValuesDocument, metadata: &ChartMetadata) {
+        if *self.writer.dialect() == CommentDialect::Bitnami {
+            self.format_sections(document, metadata);
+        }
+
+        for param in metadata.values() {
+            self.format_param(document, metadata, param);
+        }
+    }
+
+    /// Rewrite section headers and descriptions and keep one blank line around section blocks
+    fn format_sections(&self, document: &mut ValuesDocument, metadata: &ChartMetadata) {
+        // blank lines between two section blocks are normalized once
+        let mut normalized: HashSet<usize> = HashSet::new();
+
+        for section in metadata.sections() {
+            let span = section.span();
+            let indent = document.indent(span.start_line).to_string();
+
+            document.replace(
+                span.start_line,
+                span.end_line,
+                self.writer.section(&indent, section.name()),
+            );
+
+            let mut block_end = span.end_line;
+
+            if let Some(descr_span) = section.descr_span() {
+                document.replace(
+                    descr_span.start_line,
+                    descr_span.end_line,
+                    self.writer.section_descr(&indent, &section.descr()),
+                );
+
+                block_end = descr_span.end_line;
+            }
+
+            // plain comments right after the header belong to the section block
+            while self.is_plain_comment(document, metadata, block_end + 1) {
+                block_end += 1;
+            }
+
+            if normalized.insert(span.start_line) {
+                self.normalize_blank_lines(document, span.start_line);
+            }
+
+            if let Some(next_line) = document.next_content_line(block_end) {
+                if normalized.insert(next_line) {
+                    self.normalize_blank_lines(document, next_line);
+                }
+            }
+        }
+    }
+
+    /// Keep exactly one blank line before the given line (none at the beginning of the file)
+    fn normalize_blank_lines(&self, document: &mut ValuesDocument, line: usize) {
+        let mut first_blank = line;
+
+        while first_blank > 1 && document.line(first_blank - 1).is_some_and(|l| l.is_blank()) {
+            first_blank -= 1;
+        }
+
+        let blank_count = line - first_blank;
+
+        if first_blank == 1 {
+            if blank_count > 0 {
+                document.replace(first_blank, line - 1, vec![]);
+            }
+        } else if blank_count == 0 {
+            document.insert_before(line, vec![String::new()]);
+        } else if blank_count > 1 {
+            document.replace(first_blank, line - 1, vec![String::new()]);
+        }
+    }
+
+    fn format_param(
+        &self,
+        document: &mut ValuesDocument,
+        metadata: &ChartMetadata,
+        param: &Rc<ValueMetadata>,
+    ) {
+        let Some(span) = param.comment_span() else {
+            return;
+        };
+
+        let comment = ParamComment {
+            name: if param.has_inferred_name() {
+                INFERRED_NAME.to_string()
+            } else {
+                param.name().to_string()
+            },
+            modifiers: self.normalize_modifiers(param),
+            section: match self.writer.dialect() {
+                CommentDialect::HelmDocs => self.param_section(metadata, param),
+                // section headers are formatted separately
+                CommentDialect::Bitnami => None,
+            },
+            inferred: document
+                .next_content_line(span.end_line)
+                .and_then(|line| document.line(line))
+                .is_some_and(|line| line.key_path() == Some(param.name())),
+            ..ParamComment::from_metadata(param)
+        };
+
+        // the first line after the comment that is not another metadata comment
+        let mut anchor_line = span.end_line + 1;
+
+        while document
+            .line(anchor_line)
+            .is_some_and(|line| matches!(line.token, LineToken::Comment))
+        {
+            anchor_line += 1;
+        }
+
+        // params are grouped above the key or one of its parents (e.g. image.tag above image)
+        let anchored = document
+            .line(anchor_line)
+            .and_then(|line| line.key_path())
+            .is_some_and(|key_path| {
+                key_path == param.name() || path::is_nested(param.name(), key_path)
+            });
+
+        if anchored {
+            let indent = document.indent(anchor_line).to_string();
+
+            if let Some(lines) = self.writer.param(&indent, &comment) {
+                document.replace_param(param, lines);
+            }
+
+            return;
+        }
+
+        let key_line = document.key_line(param.name());
+
+        // the param is moved right above its key unless it would end up in another section
+        if let Some(key_line) = key_line.filter(|key_line| {
+            *self.writer.dialect() == CommentDialect::Bitnami
+                && !param.has_extra()
+                && self.section_line(metadata, *key_line)
+                    == self.section_line(metadata, span.start_line)
+        }) {
+            let indent = document.indent(key_line).to_string();
+
+            if let Some(lines) = self.writer.param(&indent, &comment) {
+                document.replace_param(param, vec![]);
+                document.insert_before(key_line, lines);
+            }
+
+            return;
+        }
+
+        let indent = document.indent(span.start_line).to_string();
+
+        if let Some(lines) = self.writer.param(&indent, &comment) {
+            document.replace_param(param, lines);
+        }
+    }
+
+    /// Deduplicate and sort modifiers of the param
+    fn normalize_modifiers(&self, param: &ValueMetadata) -> Vec<Modifier> {
+        let mut modifiers: Vec<Modifier> = Vec::new();
+
+        for modifier in ParamComment::from_metadata(param).modifiers {
+            if !modifiers.contains(&modifier) {
+                modifiers.push(modifier);
+            }
+        }
+
+        // types go first, then the rest of known modifiers (e.g. min before max) and unknown ones by name
+        modifiers.sort_by_key(|m| (modifier_rank(m), m.format(&self.modifiers)));
+
+        modifiers
+    }
+
+    fn param_section(&self, metadata: &ChartMetadata, param: &Rc<ValueMetadata>) -> Option<String> {
+        metadata
+            .sections()
+            .iter()
+            .find(|section| section.has_value(param))
+            .map(|section| section.name().to_string())
+    }
+
+    /// The line of the closest section header above the given line
+    fn section_line(&self, metadata: &ChartMetadata, line: usize) -> Option<usize> {
+        metadata
+            .sections()
+            .iter()
+            .map(|section| section.span().start_line)
+            .filter(|start_line| *start_line < line)
+            .max()
+    }
+
+    /// Whether the line is a comment that is not a part of any metadata
+    fn is_plain_comment(
+        &self,
+        document: &ValuesDocument,
+        metadata: &ChartMetadata,
+        line: usize,
+    ) -> bool {
+        let is_comment = document
+            .line(line)
+            .is_some_and(|doc_line| matches!(doc_line.token, LineToken::Comment));
+
+        is_comment
+            && !metadata.values().iter().any(|param| {
+                param
+                    .comment_span()
+                    .is_some_and(|span| span.start_line <= line && line <= span.end_line)
+            })
+            && !metadata
+                .sections()
+                .iter()
+                .any(|section| section.span().start_line == line)
+    }
+}
+
+fn modifier_rank(modifier: &Modifier) -> usize {
+    match modifier {
+        Modifier::Array | Modifier::Object | Modifier::String => 0,
+        Modifier::Nullable => 1,
+        Modifier::Subtree => 2,
+        Modifier::Required => 3,
+        Modifier::Default(_) => 4,
+        Modifier::Enum(_) => 5,
+        Modifier::Min(_) => 6,
+        Modifier::Max(_) => 7,
+        Modifier::Other(_) => 8,
+        // the pattern goes last, so commas in it are not followed by other modifiers
+        Modifier::Pattern(_) => 9,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::parse_chart;
+
+    fn format(content: &str) -> String {
+        let config = Config::default();
+        let mut chart = parse_chart(content, &config);
+
+        Formatter::new(&config).format(&mut chart.document, &chart.metadata);
+
+        chart.document.render()
+    }
+
+    #[test]
+    fn skip_keeps_its_description() {
+        let content = "\
+## @skip internal Not meant to be changed by users
+internal:
+  token: abc
+";
+
+        assert_eq!(format(content), content);
+    }
+
+    #[test]
+    fn modifiers_are_deduplicated_and_sorted() {
+        let content = "\
+## @param   image.tag [pattern: ^v\\d+, nullable,string, nullable]   Image tag
+image:
+  tag: v1
+";
+
+        assert_eq!(
+            format(content),
+            "\
+## @param image.tag [string, nullable, pattern: ^v\\d+] Image tag
+image:
+  tag: v1
+"
+        );
+    }
+
+    #[test]
+    fn inferred_names_are_kept() {
+        let content = "\
+## @param - Replica count
+replicas: 1
+";
+
+        assert_eq!(format(content), content);
+    }
+
+    #[test]
+    fn params_are_moved_above_their_keys() {
+        let content = "\
+## @section Main
+## @param replicas Replica count
+## @param image.tag Image tag
+replicas: 1
+image:
+  tag: v1
+";
+
+        assert_eq!(
+            format(content),
+            "\
+## @section Main
+
+## @param replicas Replica count
+replicas: 1
+image:
+  ## @param image.tag Image tag
+  tag: v1
+"
+        );
+    }
+
+    #[test]
+    fn formatting_is_idempotent() {
+        let content = "\
+## @section Main
+## @skip internal Not meant to be changed by users
+internal: {}
+## @param image.tag [nullable,string] Image tag
+##   spanning two lines
+image:
+  tag: v1
+";
+        let formatted = format(content);
+
+        assert_eq!(format(&formatted), formatted);
+    }
+}
@@ -0,0 +1,359 @@
+/*
+* Copyright 2024, Roma Hlushko
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Lossless representation of the values file.
+//! serde_yaml drops comments and formatting, so write-back features edit the file
+//! as a sequence of classified lines with their comment trivia and print it back byte-for-byte
+
+use crate::helm::locator::{KeyLocator, LineToken, LocatedKey};
+use crate::metadata::value::ValueMetadata;
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// DocLine is a single line of the values file split into tokens
+#[derive(Debug, Clone)]
+pub struct DocLine {
+    /// Leading whitespace
+    pub indent: String,
+    /// The line content without the indent, the trailing comment and the line ending
+    pub content: String,
+    /// Whitespace between the content and the trailing comment
+    pub gap: String,
+    /// The comment including the # mark (the whole line content for full-line comments)
+    pub comment: Option<String>,
+    /// The line ending (empty for the last line without a trailing newline)
+    pub eol: String,
+    pub token: LineToken,
+}
+
+impl DocLine {
+    fn parse(raw: &str, token: LineToken) -> DocLine {
+        let (line, eol) = split_eol(raw);
+        let trimmed = line.trim_start();
+        let indent = line[..line.len() - trimmed.len()].to_string();
+
+        let (content, gap, comment) = match token {
+            LineToken::Comment => (String::new(), String::new(), Some(trimmed.to_string())),
+            LineToken::Key(_) | LineToken::Other => {
+                let (content, comment) = split_comment(trimmed);
+                let gap = &content[content.trim_end().len()..];
+
+                (
+                    content.trim_end().to_string(),
+                    gap.to_string(),
+                    comment.map(|comment| comment.to_string()),
+                )
+            }
+            LineToken::Blank | LineToken::Scalar => (trimmed.to_string(), String::new(), None),
+        };
+
+        DocLine {
+            indent,
+            content,
+            gap,
+            comment,
+            eol: eol.to_string(),
+            token,
+        }
+    }
+
+    pub fn is_blank(&self) -> bool {
+        matches!(self.token, LineToken::Blank)
+    }
+
+    /// The path of the key defined on the line
+    pub fn key_path(&self) -> Option<&str> {
+        match &self.token {
+            LineToken::Key(key) => Some(&key.path),
+            _ => None,
+        }
+    }
+
+    /// The original text of the line without the line ending
+    pub fn text(&self) -> String {
+        format!(
+            "{}{}{}{}",
+            self.indent,
+            self.content,
+            self.gap,
+            self.comment.as_deref().unwrap_or_default(),
+        )
+    }
+
+    fn print(&self) -> String {
+        format!("{}{}", self.text(), self.eol)
+    }
+}
+
+/// Edit replaces a range of original lines (1-based, inclusive) with new ones.
+/// An empty range (end_line < start_line) inserts lines before the start line
+#[derive(Debug)]
+struct Edit {
+    start_line: usize,
+    end_line: usize,
+    lines: Vec<String>,
+}
+
+/// ValuesDocument holds the values file as it is and the edits to apply to it.
+/// It's the positional model of the file both values and metadata are parsed from.
+/// Edits refer to the original line numbers, so they don't affect each other
+pub struct ValuesDocument {
+    path: PathBuf,
+    lines: Vec<DocLine>,
+    edits: Vec<Edit>,
+}
+
+impl ValuesDocument {
+    pub fn load<P: AsRef<Path>>(values_path: P) -> Result<ValuesDocument> {
+        let content = fs::read_to_string(&values_path)?;
+
+        Ok(ValuesDocument::parse(values_path, &content))
+    }
+
+    pub fn parse<P: AsRef<Path>>(values_path: P, content: &str) -> ValuesDocument {
+        let tokens = KeyLocator::new().tokenize(&values_path, content);
+
+        let lines = content
+            .split_inclusive('\n')
+            .zip(tokens)
+            .map(|(raw, token)| DocLine::parse(raw, token))
+            .collect();
+
+        ValuesDocument {
+            path: values_path.as_ref().to_path_buf(),
+            lines,
+            edits: Vec::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The original lines of the document
+    pub fn lines(&self) -> &[DocLine] {
+        &self.lines
+    }
+
+    /// The original content of the document (edits are not applied)
+    pub fn source(&self) -> String {
+        self.lines.iter().map(DocLine::print).collect()
+    }
+
+    /// Keys in the order they appear in the document
+    pub fn keys(&self) -> impl Iterator<Item = &LocatedKey> {
+        self.lines.iter().filter_map(|line| match &line.token {
+            LineToken::Key(key) => Some(key),
+            _ => None,
+        })
+    }
+
+    /// The key defined on the given line (1-based)
+    pub fn key_at(&self, line: usize) -> Option<&LocatedKey> {
+        match &self.line(line)?.token {
+            LineToken::Key(key) => Some(key),
+            _ => None,
+        }
+    }
+
+    /// The key the comment on the given line (1-based) is placed above.
+    /// Other comments and blank lines may go in between, but no other content
+    pub fn key_below(&self, line: usize) -> Option<&LocatedKey> {
+        let key_line = self
+            .lines
+            .iter()
+            .enumerate()
+            .skip(line)
+            .find(|(_, doc_line)| !matches!(doc_line.token, LineToken::Blank | LineToken::Comment))
+            .map(|(idx, _)| idx + 1)?;
+
+        self.key_at(key_line)
+    }
+
+    /// The original line (1-based)
+    pub fn line(&self, line: usize) -> Option<&DocLine> {
+        line.checked_sub(1).and_then(|idx| self.lines.get(idx))
+    }
+
+    /// The line (1-based) where the key is defined
+    pub fn key_line(&self, value_path: &str) -> Option<usize> {
+        self.lines
+            .iter()
+            .position(|line| line.key_path() == Some(value_path))
+            .map(|idx| idx + 1)
+    }
+
+    /// The first non-blank line (1-based) after the given one
+    pub fn next_content_line(&self, line: usize) -> Option<usize> {
+        self.lines
+            .iter()
+            .enumerate()
+            .skip(line)
+            .find(|(_, doc_line)| !doc_line.is_blank())
+            .map(|(idx, _)| idx + 1)
+    }
+
+    /// Leading whitespace of the given line (1-based)
+    pub fn indent(&self, line: usize) -> &str {
+        self.line(line)
+            .map(|doc_line| doc_line.indent.as_str())
+            .unwrap_or_default()
+    }
+
+    /// Replace the lines (1-based, inclusive) with new ones
+    pub fn replace(&mut self, start_line: usize, end_line: usize, lines: Vec<String>) {
+        self.edits.push(Edit {
+            start_line,
+            end_line,
+            lines,
+        });
+    }
+
+    /// Insert new lines before the given line (1-based)
+    pub fn insert_before(&mut self, line: usize, lines: Vec<String>) {
+        self.replace(line, line - 1, lines);
+    }
+
+    /// Replace the metadata comment of the param (including description continuation lines)
+    pub fn replace_param(&mut self, param: &ValueMetadata, lines: Vec<String>) {
+        if let Some(span) = param.comment_span() {
+            self.replace(span.start_line, span.end_line, lines);
+        }
+    }
+
+    pub fn has_edits(&self) -> bool {
+        !self.edits.is_empty()
+    }
+
+    /// Print the document with all edits applied.
+    /// Lines that were not edited are kept byte-for-byte
+    pub fn render(&self) -> String {
+        let mut edits: Vec<&Edit> = self.edits.iter().collect();
+        // insertions go before replacements of the same line
+        edits.sort_by_key(|edit| (edit.start_line, edit.end_line >= edit.start_line));
+
+        let default_eol = self
+            .lines
+            .iter()
+            .find(|line| !line.eol.is_empty())
+            .map(|line| line.eol.as_str())
+            .unwrap_or("\n");
+
+        let mut content = String::new();
+        // the index of the next original line to print
+        let mut next_line = 0;
+
+        for edit in edits {
+            if edit.start_line <= next_line {
+                log::debug!("Skipping overlapping edit at line {}", edit.start_line);
+
+                continue;
+            }
+
+            let start_idx = (edit.start_line - 1).min(self.lines.len());
+
+            for line in &self.lines[next_line..start_idx] {
+                content.push_str(&line.print());
+            }
+
+            // inserted lines end with the document line ending,
+            // replaced ones keep the ending of the last original line
+            let last_eol = match self.lines.get(edit.end_line.wrapping_sub(1)) {
+                Some(line) if edit.end_line >= edit.start_line => line.eol.as_str(),
+                _ => default_eol,
+            };
+
+            // a line is inserted after the last line with no trailing newline
+            if start_idx == self.lines.len() && !content.is_empty() && !content.ends_with('\n') {
+                content.push_str(default_eol);
+            }
+
+            for (idx, new_line) in edit.lines.iter().enumerate() {
+                content.push_str(new_line);
+                content.push_str(if idx + 1 == edit.lines.len() {
+                    last_eol
+                } else {
+                    default_eol
+                });
+            }
+
+            next_line = start_idx.max(edit.end_line);
+        }
+
+        for line in &self.lines[next_line.min(self.lines.len())..] {
+            content.push_str(&line.print());
+        }
+
+        content
+    }
+}
+
+fn split_eol(raw: &str) -> (&str, &str) {
+    let line = raw.trim_end_matches(['\r', '\n']);
+
+    (line, &raw[line.len()..])
+}
+
+/// Split the trailing comment off the line (quoted # marks are not comments)
+fn split_comment(text: &str) -> (&str, Option<&str>) {
+    let mut quote: Option<char> = None;
+    let mut prev: Option<char> = None;
+
+    for (idx, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') if prev.is_none_or(|p| p.is_whitespace() || p == ':') => {
+                quote = Some(c)
+            }
+            (None, '#') if prev.is_none_or(|p| p.is_whitespace()) => {
+                return (&text[..idx], Some(&text[idx..]));
+            }
+            _ => {}
+        }
+
+        prev = Some(c);
+    }
+
+    (text, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(content: &str) {
+        assert_eq!(
+            ValuesDocument::parse("values.yaml", content).render(),
+            content
+        );
+    }
+
+    #[test]
+    fn render_keeps_content_byte_for_byte() {
+        round_trip("");
+        round_trip("image:\n  tag: v1  # the tag\n");
+        round_trip("image:\r\n  tag: v1\r\n\r\n## @param replicas\r\nreplicas: 1\r\n");
+        round_trip("image:\n  tag: v1");
+        round_trip("config: |\n  key: value\n  # not a comment\n\nnext: >-\n  folded\n");
+        round_trip("ports: [80,\n  443]\nlabels: {app: web,\n  tier: \"#1\"}\n");
+        round_trip("  \n\t# tabbed\nlist:\n  - a\n  - - b\n---\n");
+    }
+
+    #[test]
+    fn render_applies_edits() {
+        let mut document =
+            ValuesDocument::parse("values.yaml", "## old\r\nimage: nginx\r\ntag: v1");
+
+        document.replace(1, 1, vec!["## new".to_string()]);
+        document.insert_before(3, vec!["## @param tag".to_string()]);
+        document.insert_before(4, vec!["last: true".to_string()]);
+
+        assert_eq!(
+            document.render(),
+            "## new\r\nimage: nginx\r\n## @param tag\r\ntag: v1\r\nlast: true\r\n"
+        );
+    }
+}
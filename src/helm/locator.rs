@@ -0,0 +1,302 @@
+/*
+* Copyright 2024, Roma Hlushko
+* SPDX-License-Identifier: Apache-2.0
+*/
+use crate::helm::path;
+use crate::span::Span;
+use regex::Regex;
+use std::path::Path;
+
+/// LocatedKey is a mapping key found in the values file
+#[derive(Debug, Clone)]
+pub struct LocatedKey {
+    pub path: String,
+    pub span: Span,
+}
+
+/// LineToken tells what a line of the values file holds
+#[derive(Debug, Clone)]
+pub enum LineToken {
+    Blank,
+    /// A full-line comment
+    Comment,
+    /// A line that defines a mapping key (possibly after sequence item dashes)
+    Key(LocatedKey),
+    /// A line of a block scalar or a multiline flow collection
+    Scalar,
+    /// Any other content (e.g. scalar sequence items or document markers)
+    Other,
+}
+
+enum Frame {
+    Key { indent: usize, path: String },
+    Item { indent: usize, path: String },
+}
+
+impl Frame {
+    fn indent(&self) -> usize {
+        match self {
+            Frame::Key { indent, .. } | Frame::Item { indent, .. } => *indent,
+        }
+    }
+
+    fn path(&self) -> &str {
+        match self {
+            Frame::Key { path, .. } | Frame::Item { path, .. } => path,
+        }
+    }
+}
+
+/// KeyLocator finds positions of block mapping keys in values files.
+/// serde_yaml doesn't expose node positions, so keys are located by scanning the file line by line
+pub struct KeyLocator {
+    key_regex: Regex,
+    item_regex: Regex,
+}
+
+impl KeyLocator {
+    pub fn new() -> KeyLocator {
+        let key_regex = Regex::new(
+            r#"^("(?:[^"\\]|\\.)*"|'(?:[^']|'')*'|[^\s#'"\-\[\]{}][^#]*?|-[^\s#][^#]*?)\s*:(?:\s+(.*))?$"#,
+        )
+        .unwrap();
+        let item_regex = Regex::new(r"^-(?:\s+|$)").unwrap();
+
+        KeyLocator {
+            key_regex,
+            item_regex,
+        }
+    }
+
+    /// Classify every line of the values file (in the order of content.lines())
+    pub fn tokenize<P: AsRef<Path>>(&self, values_file: P, content: &str) -> Vec<LineToken> {
+        let mut tokens = Vec::new();
+        let mut frames: Vec<Frame> = Vec::new();
+        // the number of items seen so far per sequence path
+        let mut item_counts: Vec<(String, usize)> = Vec::new();
+
+        // lines nested deeper than this indent belong to a block scalar
+        let mut block_scalar_indent: Option<usize> = None;
+        let mut open_brackets: i32 = 0;
+
+        for (line_idx, line) in content.lines().enumerate() {
+            let trimmed = line.trim_start();
+            let mut indent = line.len() - trimmed.len();
+
+            if let Some(scalar_indent) = block_scalar_indent {
+                if trimmed.is_empty() || indent > scalar_indent {
+                    tokens.push(LineToken::Scalar);
+
+                    continue;
+                }
+
+                block_scalar_indent = None;
+            }
+
+            if open_brackets > 0 {
+                open_brackets += count_brackets(line);
+                tokens.push(LineToken::Scalar);
+
+                continue;
+            }
+
+            if trimmed.is_empty() {
+                tokens.push(LineToken::Blank);
+
+                continue;
+            }
+
+            if trimmed.starts_with('#') {
+                tokens.push(LineToken::Comment);
+
+                continue;
+            }
+
+            if trimmed.starts_with("---") {
+                tokens.push(LineToken::Other);
+
+                continue;
+            }
+
+            let mut rest = trimmed;
+
+            // sequence items (there may be several on one line, e.g. "- - a")
+            while let Some(item_match) = self.item_regex.find(rest) {
+                while frames.last().is_some_and(|frame| {
+                    frame.indent() > indent
+                        || (frame.indent() == indent && matches!(frame, Frame::Item { .. }))
+                }) {
+                    frames.pop();
+                }
+
+                let seq_path = frames
+                    .last()
+                    .map(|f| f.path().to_string())
+                    .unwrap_or_default();
+                let item_idx = match item_counts.iter_mut().find(|(path, _)| *path == seq_path) {
+                    Some((_, count)) => {
+                        *count += 1;
+                        *count - 1
+                    }
+                    None => {
+                        item_counts.push((seq_path.clone(), 1));
+                        0
+                    }
+                };
+
+                frames.push(Frame::Item {
+                    indent,
+                    path: path::join_index(&seq_path, item_idx),
+                });
+
+                indent += item_match.end();
+                rest = &rest[item_match.end()..];
+            }
+
+            let Some(captures) = self.key_regex.captures(rest) else {
+                open_brackets += count_brackets(rest);
+                tokens.push(LineToken::Other);
+
+                continue;
+            };
+
+            while frames.last().is_some_and(|frame| frame.indent() >= indent) {
+                frames.pop();
+            }
+
+            let raw_key = captures.get(1).unwrap();
+            let key = unquote(raw_key.as_str());
+
+            let path = match frames.last() {
+                Some(frame) => path::join(frame.path(), &key),
+                None => path::join("", &key),
+            };
+
+            let start_column = line[..indent].chars().count() + 1;
+            let end_column = start_column + raw_key.as_str().chars().count();
+
+            tokens.push(LineToken::Key(LocatedKey {
+                path: path.clone(),
+                span: Span::line(&values_file, line_idx + 1, start_column, end_column),
+            }));
+
+            let value = captures.get(2).map(|v| v.as_str().trim()).unwrap_or("");
+
+            if value.starts_with('|') || value.starts_with('>') {
+                block_scalar_indent = Some(indent);
+            } else {
+                open_brackets += count_brackets(value);
+            }
+
+            frames.push(Frame::Key { indent, path });
+        }
+
+        tokens
+    }
+}
+
+/// Count unbalanced flow collection brackets (e.g. in multiline [a, b] or {a: b} values)
+fn count_brackets(text: &str) -> i32 {
+    let mut count = 0;
+    let mut quote: Option<char> = None;
+
+    for c in text.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '#') => break,
+            (None, '[') | (None, '{') => count += 1,
+            (None, ']') | (None, '}') => count -= 1,
+            _ => {}
+        }
+    }
+
+    count
+}
+
+fn unquote(key: &str) -> String {
+    if key.len() >= 2 && key.starts_with('"') && key.ends_with('"') {
+        return key[1..key.len() - 1].replace("\\\"", "\"");
+    }
+
+    if key.len() >= 2 && key.starts_with('\'') && key.ends_with('\'') {
+        return key[1..key.len() - 1].replace("''", "'");
+    }
+
+    key.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALUES: &str = "\
+## @param image.tag Image tag
+image:
+  tag: v1
+\"quoted.key\": true
+config: |
+  key: value
+
+  # not a comment
+ports: [80,
+  443]
+hosts:
+  - host: example.com
+    paths:
+      - /
+  - host: example.org
+";
+
+    /// Token kinds with key paths (e.g. key:image.tag)
+    fn tokens(content: &str) -> Vec<String> {
+        KeyLocator::new()
+            .tokenize("values.yaml", content)
+            .into_iter()
+            .map(|token| match token {
+                LineToken::Blank => "blank".to_string(),
+                LineToken::Comment => "comment".to_string(),
+                LineToken::Key(key) => format!("key:{}", key.path),
+                LineToken::Scalar => "scalar".to_string(),
+                LineToken::Other => "other".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tokenize_classifies_lines() {
+        assert_eq!(
+            tokens(VALUES),
+            vec![
+                "comment",
+                "key:image",
+                "key:image.tag",
+                r#"key:["quoted.key"]"#,
+                "key:config",
+                "scalar",
+                "scalar",
+                "scalar",
+                "key:ports",
+                "scalar",
+                "key:hosts",
+                "key:hosts[0].host",
+                "key:hosts[0].paths",
+                "other",
+                "key:hosts[1].host",
+            ]
+        );
+    }
+
+    #[test]
+    fn locate_keys_with_columns() {
+        let tokens = KeyLocator::new().tokenize("values.yaml", VALUES);
+
+        let LineToken::Key(tag) = &tokens[2] else {
+            panic!("image.tag is not located");
+        };
+
+        assert_eq!(tag.path, "image.tag");
+        assert_eq!(tag.span, Span::line("values.yaml", 3, 3, 6));
+    }
+}
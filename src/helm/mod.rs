@@ -2,5 +2,8 @@
 * Copyright 2024, Roma Hlushko
 * SPDX-License-Identifier: Apache-2.0
 */
+pub mod document;
+pub mod locator;
 pub mod parser;
-mod values;
+pub mod path;
+pub mod values;
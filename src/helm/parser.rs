@@ -2,60 +2,78 @@
 * Copyright 2024, Roma Hlushko
 * SPDX-License-Identifier: Apache-2.0
 */
+use crate::diagnostics::{Diagnostic, Diagnostics, Rule};
+use crate::helm::document::ValuesDocument;
+use crate::helm::path;
 use crate::helm::values::ChartValues;
 use anyhow::Result;
 use serde_yaml::{Mapping, Value};
-use std::fmt::Debug;
 use std::path::Path;
-use std::{fmt, fs};
-use thiserror::Error;
-
-#[derive(Error, Debug)]
-pub struct ValuesParseError {
-    message: String,
-}
-
-// Implement Display for your custom error
-impl fmt::Display for ValuesParseError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.message)
-    }
-}
-
-impl ValuesParseError {
-    pub fn new(msg: String) -> Self {
-        ValuesParseError { message: msg }
-    }
-}
 
 /// ValuesParser parses values.yaml file
-pub struct ValuesParser {
-    delimiter: String,
-}
+pub struct ValuesParser {}
 
 impl ValuesParser {
     pub fn new() -> ValuesParser {
-        ValuesParser {
-            delimiter: ".".to_string(),
-        }
+        ValuesParser {}
     }
 
-    pub fn parse<P: AsRef<Path> + Debug + Clone>(&self, values_file: P) -> Result<ChartValues> {
-        let content = fs::read_to_string(values_file.clone())?;
-        let values_map: Value = serde_yaml::from_str(&content)?;
+    /// Parse values of the document. Key positions are taken from the document lines
+    pub fn parse(
+        &self,
+        document: &ValuesDocument,
+        diagnostics: &Diagnostics,
+    ) -> Result<ChartValues> {
+        let values_file = document.path();
+
+        let values_map: Value = match serde_yaml::from_str(&document.source()) {
+            Ok(values_map) => values_map,
+            Err(err) => {
+                let mut diagnostic = Diagnostic::error(
+                    Rule::ParseError,
+                    values_file,
+                    format!("Failed to parse the values file: {}", err),
+                );
+
+                if let Some(location) = err.location() {
+                    diagnostic = diagnostic.with_line(location.line());
+                }
+
+                return Err(diagnostics.report_failure(diagnostic).into());
+            }
+        };
 
         let values = ChartValues::new();
         let curr_path = "";
 
-        log::debug!("Processing Helm values.yaml: {:?}", values_file.clone());
+        log::debug!("Processing Helm values.yaml: {:?}", values_file);
+
+        values.set_root(values_map.clone());
 
         if let Value::Mapping(values_map) = values_map {
-            self.process_map(curr_path, &values, &values_map)?;
+            self.process_map(
+                curr_path,
+                &values,
+                &values_map,
+                values_file,
+                false,
+                diagnostics,
+            )?;
+
+            for key in document.keys() {
+                values.add_key(key.clone());
+            }
 
             return Ok(values);
         }
 
-        Err(ValuesParseError::new("Helm values.yaml should be a map".to_string()).into())
+        Err(diagnostics
+            .report_failure(Diagnostic::error(
+                Rule::ParseError,
+                values_file,
+                "Helm values.yaml should be a map".to_string(),
+            ))
+            .into())
     }
 
     fn process_map(
@@ -63,34 +81,82 @@ impl ValuesParser {
         parent_path: &str,
         values: &ChartValues,
         values_map: &Mapping,
+        values_file: &Path,
+        in_list: bool,
+        diagnostics: &Diagnostics,
     ) -> Result<()> {
-        let curr_path = if parent_path.is_empty() {
-            parent_path.to_string()
-        } else {
-            format!("{}{}", parent_path, self.delimiter)
-        };
-
         for (key, value) in values_map {
-            let path = format!(
-                "{}{}",
-                curr_path,
-                key.as_str()
-                    .ok_or(ValuesParseError::new(
-                        "Failed to process values.yaml key".to_string()
-                    ))
-                    .unwrap(),
-            );
-
-            // If the value is also a mapping, you can recursively enumerate it
+            // Helm treats scalar keys as strings (e.g. 80: http)
+            let key = match key {
+                Value::String(key) => key.clone(),
+                Value::Number(key) => key.to_string(),
+                Value::Bool(key) => key.to_string(),
+                _ => {
+                    diagnostics.report(Diagnostic::error(
+                        Rule::ParseError,
+                        values_file,
+                        format!(
+                            "Failed to process a key of the {} value: only scalar keys are supported",
+                            if parent_path.is_empty() { "root" } else { parent_path }
+                        ),
+                    ));
+
+                    continue;
+                }
+            };
+
+            let path = path::join(parent_path, &key);
+
+            // If the value is also a mapping, you can recursively enumerate it.
+            // Empty mappings (e.g. podAnnotations: {}) are kept as values on their own
             if let Value::Mapping(ref nested_map) = value {
-                self.process_map(&path, values, nested_map)?;
+                if !nested_map.is_empty() {
+                    self.process_map(&path, values, nested_map, values_file, in_list, diagnostics)?;
 
-                continue;
+                    continue;
+                }
             }
 
             log::debug!("Processing value {}: {:?}", path, value);
 
-            values.insert(path, value.clone());
+            if in_list {
+                values.insert_item(path.clone(), value.clone());
+            } else {
+                values.insert(path.clone(), value.clone());
+            }
+
+            if let Value::Sequence(items) = value {
+                self.process_sequence(&path, values, items, values_file, diagnostics)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lists are kept as values, but their items are processed as well,
+    /// so item values can be referred by the index (e.g. ports[0].name)
+    fn process_sequence(
+        &self,
+        parent_path: &str,
+        values: &ChartValues,
+        items: &[Value],
+        values_file: &Path,
+        diagnostics: &Diagnostics,
+    ) -> Result<()> {
+        for (idx, item) in items.iter().enumerate() {
+            let path = path::join_index(parent_path, idx);
+
+            match item {
+                Value::Mapping(nested_map) if !nested_map.is_empty() => {
+                    self.process_map(&path, values, nested_map, values_file, true, diagnostics)?;
+                }
+                Value::Sequence(nested_items) => {
+                    values.insert_item(path.clone(), item.clone());
+
+                    self.process_sequence(&path, values, nested_items, values_file, diagnostics)?;
+                }
+                _ => values.insert_item(path, item.clone()),
+            }
         }
 
         Ok(())
@@ -0,0 +1,377 @@
+/*
+* Copyright 2024, Roma Hlushko
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Value paths point to values in the values file (e.g. image.tag).
+//! Keys that can't be told apart from the path syntax are written in brackets
+//! (e.g. podAnnotations["prometheus.io/scrape"]).
+//! List items are referred by their index (e.g. ports[0].name) or by a wildcard that matches all of them (e.g. ports[*].name)
+
+use std::fmt;
+use thiserror::Error;
+
+pub const DELIMITER: char = '.';
+pub const WILDCARD: &str = "*";
+/// Marks the path that refers to the whole subtree (e.g. resources.*)
+pub const SUBTREE_SUFFIX: &str = ".*";
+
+/// Segment is a single step in the value path
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+impl Segment {
+    /// Check if the segment (that may be a wildcard) matches the other one
+    fn matches(&self, other: &Segment) -> bool {
+        match (self, other) {
+            (Segment::Wildcard, Segment::Index(_)) | (Segment::Wildcard, Segment::Wildcard) => true,
+            _ => self == other,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub struct PathError {
+    message: String,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl PathError {
+    pub fn new(msg: String) -> Self {
+        PathError { message: msg }
+    }
+}
+
+/// Check if the key can be written as is (without brackets)
+fn is_plain_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Format a key as a path segment
+pub fn format_key(key: &str) -> String {
+    if is_plain_key(key) {
+        return key.to_string();
+    }
+
+    format!("[\"{}\"]", key.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Append the list item index to the parent path
+pub fn join_index(parent: &str, index: usize) -> String {
+    format!("{}[{}]", parent, index)
+}
+
+/// Append the key to the parent path
+pub fn join(parent: &str, key: &str) -> String {
+    let segment = format_key(key);
+
+    if parent.is_empty() || segment.starts_with('[') {
+        return format!("{}{}", parent, segment);
+    }
+
+    format!("{}{}{}", parent, DELIMITER, segment)
+}
+
+/// Split the path into segments
+pub fn parse(path: &str) -> Result<Vec<Segment>, PathError> {
+    let mut keys = Vec::new();
+    let mut chars = path.chars().peekable();
+    let mut key = String::new();
+    // whether the previous segment was written in brackets
+    let mut bracketed = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => {
+                if key.is_empty() && !bracketed {
+                    return Err(PathError::new(format!("{}: empty key", path)));
+                }
+
+                if !key.is_empty() {
+                    keys.push(Segment::Key(std::mem::take(&mut key)));
+                }
+
+                bracketed = false;
+
+                if chars.peek().is_none() {
+                    return Err(PathError::new(format!("{}: trailing delimiter", path)));
+                }
+            }
+            '[' => {
+                if !key.is_empty() {
+                    keys.push(Segment::Key(std::mem::take(&mut key)));
+                }
+
+                bracketed = true;
+
+                let quote = match chars.peek() {
+                    Some(&q) if q == '"' || q == '\'' => q,
+                    _ => {
+                        keys.push(parse_item(path, &mut chars)?);
+
+                        continue;
+                    }
+                };
+
+                chars.next();
+
+                let mut bracket_key = String::new();
+                let mut closed = false;
+
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            if let Some(escaped) = chars.next() {
+                                bracket_key.push(escaped);
+                            }
+                        }
+                        c if c == quote => {
+                            closed = true;
+                            break;
+                        }
+                        c => bracket_key.push(c),
+                    }
+                }
+
+                if !closed || chars.next() != Some(']') {
+                    return Err(PathError::new(format!("{}: unclosed bracket", path)));
+                }
+
+                keys.push(Segment::Key(bracket_key));
+            }
+            c => {
+                if bracketed {
+                    return Err(PathError::new(format!(
+                        "{}: a delimiter is expected after ]",
+                        path
+                    )));
+                }
+
+                key.push(c)
+            }
+        }
+    }
+
+    if !key.is_empty() {
+        keys.push(Segment::Key(key));
+    }
+
+    if keys.is_empty() {
+        return Err(PathError::new("empty path".to_string()));
+    }
+
+    Ok(keys)
+}
+
+/// Parse the list item reference (e.g. [0] or [*]) that goes after the opening bracket
+fn parse_item(
+    path: &str,
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+) -> Result<Segment, PathError> {
+    let mut item = String::new();
+
+    for c in chars.by_ref() {
+        if c == ']' {
+            if item == WILDCARD {
+                return Ok(Segment::Wildcard);
+            }
+
+            return item.parse::<usize>().map(Segment::Index).map_err(|_| {
+                PathError::new(format!(
+                    "{}: [{}] is neither a list index nor a quoted key",
+                    path, item
+                ))
+            });
+        }
+
+        item.push(c);
+    }
+
+    Err(PathError::new(format!("{}: unclosed bracket", path)))
+}
+
+/// Build the path out of segments
+pub fn format(segments: &[Segment]) -> String {
+    segments
+        .iter()
+        .fold(String::new(), |parent, segment| match segment {
+            Segment::Key(key) => join(&parent, key),
+            Segment::Index(index) => join_index(&parent, *index),
+            Segment::Wildcard => format!("{}[{}]", parent, WILDCARD),
+        })
+}
+
+/// Strip the subtree suffix from the path if it's there (e.g. resources.* becomes resources)
+pub fn strip_subtree(path: &str) -> Option<&str> {
+    path.strip_suffix(SUBTREE_SUFFIX)
+        .filter(|subtree_path| !subtree_path.is_empty())
+}
+
+/// Bring the path to its canonical form (e.g. a['b'] becomes a.b)
+pub fn normalize(path: &str) -> Result<String, PathError> {
+    Ok(format(&parse(path)?))
+}
+
+/// ValuePath is a path parsed into segments once, so it can be matched against many others cheaply
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValuePath {
+    path: String,
+    /// None when the path is malformed, so it only matches the same path
+    segments: Option<Vec<Segment>>,
+}
+
+impl ValuePath {
+    pub fn new(path: &str) -> ValuePath {
+        ValuePath {
+            path: path.to_string(),
+            segments: parse(path).ok(),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.path
+    }
+
+    /// Check if the pattern (a path that may contain wildcards) matches the beginning of the path.
+    /// Returns the number of path segments left unmatched
+    fn match_prefix(&self, pattern: &ValuePath) -> Option<usize> {
+        let (Some(path), Some(pattern)) = (&self.segments, &pattern.segments) else {
+            return None;
+        };
+
+        if pattern.len() > path.len() {
+            return None;
+        }
+
+        pattern
+            .iter()
+            .zip(path.iter())
+            .all(|(pattern, segment)| pattern.matches(segment))
+            .then_some(path.len() - pattern.len())
+    }
+
+    /// Check if the pattern (a path that may contain wildcards) points to this path
+    pub fn matches(&self, pattern: &ValuePath) -> bool {
+        self.path == pattern.path || self.match_prefix(pattern) == Some(0)
+    }
+
+    /// Check if this path points to a value nested into the parent one (that may contain wildcards)
+    pub fn is_nested(&self, parent: &ValuePath) -> bool {
+        self.match_prefix(parent).is_some_and(|left| left > 0)
+    }
+}
+
+impl fmt::Display for ValuePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path)
+    }
+}
+
+/// Check if the path points to a value nested into the parent one (that may contain wildcards)
+pub fn is_nested(path: &str, parent: &str) -> bool {
+    ValuePath::new(path).is_nested(&ValuePath::new(parent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(key: &str) -> Segment {
+        Segment::Key(key.to_string())
+    }
+
+    #[test]
+    fn parse_quoted_and_indexed_paths() {
+        assert_eq!(parse("image.tag").unwrap(), vec![key("image"), key("tag")]);
+        assert_eq!(
+            parse(r#"podAnnotations["prometheus.io/scrape"]"#).unwrap(),
+            vec![key("podAnnotations"), key("prometheus.io/scrape")]
+        );
+        assert_eq!(
+            parse(r#"labels['app.kubernetes.io/name'].value"#).unwrap(),
+            vec![key("labels"), key("app.kubernetes.io/name"), key("value")]
+        );
+        assert_eq!(
+            parse(r#"a["say \"hi\" \\ there"]"#).unwrap(),
+            vec![key("a"), key(r#"say "hi" \ there"#)]
+        );
+        assert_eq!(
+            parse("ports[0].name").unwrap(),
+            vec![key("ports"), Segment::Index(0), key("name")]
+        );
+        assert_eq!(
+            parse("hosts[*].paths[*]").unwrap(),
+            vec![
+                key("hosts"),
+                Segment::Wildcard,
+                key("paths"),
+                Segment::Wildcard
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_rejects_malformed_paths() {
+        for path in [
+            "",
+            "image..tag",
+            "image.",
+            r#"a["b"c"#,
+            r#"a["b"]c"#,
+            "ports[x]",
+            "ports[0",
+        ] {
+            assert!(parse(path).is_err(), "{} is parsed", path);
+        }
+    }
+
+    #[test]
+    fn format_quotes_keys_when_needed() {
+        assert_eq!(format(&[key("image"), key("tag")]), "image.tag");
+        assert_eq!(
+            format(&[key("podAnnotations"), key("prometheus.io/scrape")]),
+            r#"podAnnotations["prometheus.io/scrape"]"#
+        );
+        assert_eq!(
+            format(&[key("a"), key(r#"say "hi" \ there"#)]),
+            r#"a["say \"hi\" \\ there"]"#
+        );
+        assert_eq!(
+            format(&[key("ports"), Segment::Index(0), key("name")]),
+            "ports[0].name"
+        );
+        assert_eq!(format(&[key("hosts"), Segment::Wildcard]), "hosts[*]");
+    }
+
+    #[test]
+    fn normalize_brings_paths_to_canonical_form() {
+        assert_eq!(normalize("a['b']").unwrap(), "a.b");
+        assert_eq!(normalize(r#"a["b"].c"#).unwrap(), "a.b.c");
+        assert_eq!(normalize("a['b.c']").unwrap(), r#"a["b.c"]"#);
+        assert_eq!(normalize("ports[0].name").unwrap(), "ports[0].name");
+        assert!(normalize("a..b").is_err());
+    }
+
+    #[test]
+    fn wildcards_match_items() {
+        let name = ValuePath::new("ports[*].name");
+
+        assert!(ValuePath::new("ports[0].name").matches(&name));
+        assert!(!ValuePath::new("ports[0].port").matches(&name));
+        assert!(!ValuePath::new("ports[0]").matches(&name));
+        assert!(ValuePath::new("a..b").matches(&ValuePath::new("a..b")));
+        assert!(ValuePath::new("ports[0].name").is_nested(&ValuePath::new("ports[*]")));
+        assert!(!ValuePath::new("ports[*]").is_nested(&ValuePath::new("ports[*]")));
+    }
+}
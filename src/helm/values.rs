@@ -3,23 +3,141 @@
 * SPDX-License-Identifier: Apache-2.0
 */
 
+use crate::helm::locator::LocatedKey;
+use crate::helm::path::{self, Segment, ValuePath};
+use crate::span::Span;
 use serde_yaml::Value;
 use std::cell::RefCell;
 use std::collections::HashMap;
 
-/// HelmValues holds flatten path to a helm value (e.g. dot-separated path like image.tag) and it's value
+/// The type of the YAML value (e.g. string, int, list or map)
+pub fn value_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(number) if number.is_f64() => "float",
+        Value::Number(_) => "int",
+        Value::String(_) => "string",
+        Value::Sequence(_) => "list",
+        Value::Mapping(_) => "map",
+        Value::Tagged(tagged) => value_type(&tagged.value),
+    }
+}
+
+/// HelmValues holds flatten path to a helm value (e.g. dot-separated path like image.tag) and it's value.
+/// Lists are values on their own, but their items are flatten too (e.g. ports[0].name), so they can be documented separately
 pub struct ChartValues {
     values: RefCell<HashMap<String, Value>>,
+    paths: RefCell<Vec<ValuePath>>,
+    item_paths: RefCell<Vec<ValuePath>>,
+    spans: RefCell<HashMap<String, Span>>,
+    root: RefCell<Value>,
 }
 
 impl ChartValues {
     pub fn new() -> ChartValues {
         ChartValues {
             values: RefCell::new(HashMap::new()),
+            paths: RefCell::new(Vec::new()),
+            item_paths: RefCell::new(Vec::new()),
+            spans: RefCell::new(HashMap::new()),
+            root: RefCell::new(Value::Null),
         }
     }
 
     pub fn insert(&self, value_path: String, value: Value) {
-        self.values.borrow_mut().insert(value_path, value);
+        if self
+            .values
+            .borrow_mut()
+            .insert(value_path.clone(), value)
+            .is_none()
+        {
+            self.paths.borrow_mut().push(ValuePath::new(&value_path));
+        }
+    }
+
+    /// Insert a value nested into a list item (e.g. ports[0].name)
+    pub fn insert_item(&self, value_path: String, value: Value) {
+        if self
+            .values
+            .borrow_mut()
+            .insert(value_path.clone(), value)
+            .is_none()
+        {
+            self.item_paths
+                .borrow_mut()
+                .push(ValuePath::new(&value_path));
+        }
+    }
+
+    /// Value paths in the order they appear in the values file
+    pub fn paths(&self) -> Vec<ValuePath> {
+        self.paths.borrow().clone()
+    }
+
+    /// Paths of values nested into list items in the order they appear in the values file
+    pub fn item_paths(&self) -> Vec<ValuePath> {
+        self.item_paths.borrow().clone()
+    }
+
+    /// Paths of values nested into the given list
+    pub fn list_item_paths(&self, list_path: &ValuePath) -> Vec<ValuePath> {
+        self.item_paths
+            .borrow()
+            .iter()
+            .filter(|path| path.is_nested(list_path))
+            .cloned()
+            .collect()
+    }
+
+    pub fn get(&self, value_path: &str) -> Option<Value> {
+        self.values.borrow().get(value_path).cloned()
+    }
+
+    /// Keep the whole values tree, so values that are not leaves can be looked up too
+    pub fn set_root(&self, root: Value) {
+        *self.root.borrow_mut() = root;
+    }
+
+    /// Get the whole subtree under the path (e.g. resources)
+    pub fn subtree(&self, value_path: &str) -> Option<Value> {
+        let segments = path::parse(value_path).ok()?;
+        let root = self.root.borrow();
+        let mut node = &*root;
+
+        for segment in segments {
+            node = match (segment, node) {
+                (Segment::Key(key), Value::Mapping(map)) => map
+                    .iter()
+                    .find(|(k, _)| match k {
+                        Value::String(k) => *k == key,
+                        Value::Number(k) => k.to_string() == key,
+                        Value::Bool(k) => k.to_string() == key,
+                        _ => false,
+                    })
+                    .map(|(_, v)| v)?,
+                (Segment::Index(idx), Value::Sequence(items)) => items.get(idx)?,
+                _ => return None,
+            };
+        }
+
+        Some(node.clone())
+    }
+
+    /// Check if the path points to a list without items
+    pub fn is_empty_list(&self, value_path: &str) -> bool {
+        matches!(
+            self.values.borrow().get(value_path),
+            Some(Value::Sequence(items)) if items.is_empty()
+        )
+    }
+
+    /// Add the position of the key in the values file
+    pub fn add_key(&self, key: LocatedKey) {
+        self.spans.borrow_mut().insert(key.path, key.span);
+    }
+
+    pub fn span(&self, value_path: &str) -> Option<Span> {
+        self.spans.borrow().get(value_path).cloned()
     }
 }
@@ -3,22 +3,102 @@
 * SPDX-License-Identifier: Apache-2.0
 */
 use clap::Parser;
-use std::process;
+use std::path::Path;
+use std::{fs, process};
+mod check;
 mod cli;
 mod config;
+mod diagnostics;
+mod fix;
+mod fmt;
 mod helm;
 mod logging;
 mod markdown;
 mod metadata;
+mod migrate;
+mod schema;
+mod span;
+#[cfg(test)]
+mod testing;
 
+use crate::check::checker::ValuesChecker;
 use crate::cli::Commands;
 use crate::config::Config;
 
+use crate::diagnostics::report::{DiagnosticsReporter, Format};
+use crate::diagnostics::{Diagnostic, Diagnostics, ReportedError, Rule, Severity};
+use crate::fix::fixer::{self, Fixer};
+use crate::fmt::formatter::Formatter;
+use crate::helm::document::ValuesDocument;
 use crate::helm::parser::ValuesParser;
+use crate::helm::values::ChartValues;
 use crate::markdown::render::MarkdownRenderer;
+use crate::metadata::chart::ChartMetadata;
 use crate::metadata::parser::MetadataParser;
+use crate::migrate::migrator::Migrator;
+use crate::schema::render::SchemaRenderer;
 use logging::setup_logging;
 
+/// Print collected diagnostics and exit.
+/// Errors that were not reported as diagnostics are logged as is
+fn exit_with_error(
+    reporter: &DiagnosticsReporter,
+    diagnostics: &Diagnostics,
+    context: &str,
+    err: anyhow::Error,
+) -> ! {
+    if err.downcast_ref::<ReportedError>().is_none() {
+        log::error!("{}: {}", context, err);
+    }
+
+    let _ = reporter.report(diagnostics);
+
+    process::exit(1);
+}
+
+/// Read the values file or exit with the collected diagnostics
+fn load_document(
+    values: &Path,
+    reporter: &DiagnosticsReporter,
+    diagnostics: &Diagnostics,
+) -> ValuesDocument {
+    match ValuesDocument::load(values) {
+        Ok(document) => document,
+        Err(err) => {
+            let err = diagnostics.report_failure(Diagnostic::error(
+                Rule::ParseError,
+                values,
+                format!("Failed to read the values file: {}", err),
+            ));
+
+            exit_with_error(
+                reporter,
+                diagnostics,
+                "Failed to read values file",
+                err.into(),
+            )
+        }
+    }
+}
+
+/// Parse values of the document and their metadata.
+/// Exits with the collected diagnostics if values can't be parsed
+fn parse_chart(
+    document: &ValuesDocument,
+    config: &Config,
+    reporter: &DiagnosticsReporter,
+    diagnostics: &Diagnostics,
+) -> (ChartValues, ChartMetadata) {
+    let chart_values = match ValuesParser::new().parse(document, diagnostics) {
+        Ok(chart_values) => chart_values,
+        Err(err) => exit_with_error(reporter, diagnostics, "Failed to parse values file", err),
+    };
+
+    let metadata = MetadataParser::new(config).parse(document, &chart_values, diagnostics);
+
+    (chart_values, metadata)
+}
+
 fn main() {
     let cli = cli::Cli::parse();
 
@@ -36,33 +116,278 @@ fn main() {
 
     match &cli.command {
         Some(Commands::Gen { markdown, values }) => {
-            let metadata_parser = MetadataParser::new(&config);
-            let values_parser = ValuesParser::new();
-            let renderer = MarkdownRenderer::new(&config);
+            let diagnostics = Diagnostics::new();
+            let reporter = DiagnosticsReporter::new(Format::Text);
 
-            let _ = values_parser.parse(values);
-
-            let _ = match metadata_parser.parse(values) {
-                Ok(metadata) => metadata,
+            let renderer = match MarkdownRenderer::new(&config) {
+                Ok(renderer) => renderer,
                 Err(err) => {
-                    log::error!("Failed to parse values metadata: {}", err);
+                    log::error!("Failed to init markdown renderer: {}", err);
 
                     process::exit(1);
                 }
             };
 
-            let _ = renderer.render(markdown);
+            let document = load_document(values, &reporter, &diagnostics);
+            let (chart_values, metadata) = parse_chart(&document, &config, &reporter, &diagnostics);
+
+            if let Err(err) = renderer.render(markdown, &metadata, &chart_values, &diagnostics) {
+                exit_with_error(
+                    &reporter,
+                    &diagnostics,
+                    "Failed to render markdown file",
+                    err,
+                );
+            }
+
+            let _ = reporter.report(&diagnostics);
+        }
+        Some(Commands::Schema { values, output }) => {
+            let diagnostics = Diagnostics::new();
+            let reporter = DiagnosticsReporter::new(Format::Text);
+
+            let renderer = SchemaRenderer::new();
 
-            ()
+            let document = load_document(values, &reporter, &diagnostics);
+            let (chart_values, metadata) = parse_chart(&document, &config, &reporter, &diagnostics);
+
+            let schema_path = output
+                .clone()
+                .unwrap_or_else(|| values.with_file_name("values.schema.json"));
+
+            if let Err(err) = renderer.render(&schema_path, &metadata, &chart_values) {
+                exit_with_error(
+                    &reporter,
+                    &diagnostics,
+                    "Failed to render values schema",
+                    err,
+                );
+            }
+
+            let _ = reporter.report(&diagnostics);
         }
         Some(Commands::Check {
             values,
-            markdown: _,
-            no_missing: _,
+            markdown,
+            no_missing,
+            format,
+        }) => {
+            let diagnostics = Diagnostics::new();
+            let reporter = DiagnosticsReporter::new(*format);
+
+            let checker = ValuesChecker::new();
+            let renderer = match MarkdownRenderer::new(&config) {
+                Ok(renderer) => renderer,
+                Err(err) => {
+                    log::error!("Failed to init markdown renderer: {}", err);
+
+                    process::exit(1);
+                }
+            };
+
+            let document = load_document(values, &reporter, &diagnostics);
+            let (chart_values, metadata) = parse_chart(&document, &config, &reporter, &diagnostics);
+
+            let missing_severity = if *no_missing {
+                Severity::Error
+            } else {
+                Severity::Warning
+            };
+
+            checker.check_missing(
+                values,
+                &metadata,
+                &chart_values,
+                missing_severity,
+                &diagnostics,
+            );
+            checker.check_stale(values, &metadata, &chart_values, &diagnostics);
+            checker.check_constraints(values, &metadata, &chart_values, &diagnostics);
+            checker.check_required(values, &metadata, &chart_values, &diagnostics);
+            checker.check_types(values, &metadata, &chart_values, &diagnostics);
+
+            if let Err(err) =
+                checker.check_drift(markdown, &renderer, &metadata, &chart_values, &diagnostics)
+            {
+                exit_with_error(
+                    &reporter,
+                    &diagnostics,
+                    "Failed to render markdown file",
+                    err,
+                );
+            }
+
+            if let Err(err) = reporter.report(&diagnostics) {
+                log::error!("Failed to report diagnostics: {}", err);
+
+                process::exit(1);
+            }
+
+            if diagnostics.has_errors() {
+                process::exit(1);
+            }
+
+            if *format == Format::Text && diagnostics.items().is_empty() {
+                log::info!("All chart values are documented");
+            }
+        }
+        Some(Commands::Fix {
+            values,
+            prune,
+            dry_run,
+        }) => {
+            let diagnostics = Diagnostics::new();
+            let reporter = DiagnosticsReporter::new(Format::Text);
+
+            let fixer = Fixer::new(&config);
+
+            let mut document = load_document(values, &reporter, &diagnostics);
+            let (chart_values, metadata) = parse_chart(&document, &config, &reporter, &diagnostics);
+
+            let added = fixer.add_stubs(&mut document, &metadata, &chart_values);
+            let removed = if *prune {
+                fixer.prune(&mut document, &metadata, &chart_values)
+            } else {
+                0
+            };
+
+            let _ = reporter.report(&diagnostics);
+
+            if !document.has_edits() {
+                log::info!("Nothing to fix");
+
+                return;
+            }
+
+            let fixed_content = document.render();
+
+            if *dry_run {
+                print!(
+                    "{}",
+                    fixer::diff(
+                        &values.to_string_lossy(),
+                        &document.source(),
+                        &fixed_content
+                    )
+                );
+
+                return;
+            }
+
+            if let Err(err) = fs::write(values, fixed_content) {
+                exit_with_error(
+                    &reporter,
+                    &diagnostics,
+                    "Failed to write values file",
+                    err.into(),
+                );
+            }
+
+            log::info!(
+                "Added {} param stubs, removed {} stale params",
+                added,
+                removed
+            );
+        }
+        Some(Commands::Fmt { values, check }) => {
+            let diagnostics = Diagnostics::new();
+            let reporter = DiagnosticsReporter::new(Format::Text);
+
+            let formatter = Formatter::new(&config);
+
+            let mut document = load_document(values, &reporter, &diagnostics);
+            let (_, metadata) = parse_chart(&document, &config, &reporter, &diagnostics);
+
+            let current_content = document.source();
+
+            formatter.format(&mut document, &metadata);
+
+            let formatted_content = document.render();
+
+            let _ = reporter.report(&diagnostics);
+
+            if formatted_content == current_content {
+                log::info!("The values metadata is formatted");
+
+                return;
+            }
+
+            if *check {
+                print!(
+                    "{}",
+                    fixer::diff(
+                        &values.to_string_lossy(),
+                        &current_content,
+                        &formatted_content
+                    )
+                );
+
+                log::error!("The values metadata is not formatted");
+
+                process::exit(1);
+            }
+
+            if let Err(err) = fs::write(values, formatted_content) {
+                exit_with_error(
+                    &reporter,
+                    &diagnostics,
+                    "Failed to write values file",
+                    err.into(),
+                );
+            }
+
+            log::info!("The values metadata is formatted");
+        }
+        Some(Commands::Migrate {
+            values,
+            from,
+            to,
+            from_config,
         }) => {
-            println!("Lint: {}", values.to_string_lossy());
+            let diagnostics = Diagnostics::new();
+            let reporter = DiagnosticsReporter::new(Format::Text);
+
+            let mut source_config = match from_config {
+                Some(from_config) => match Config::load(Some(from_config.clone())) {
+                    Ok(source_config) => source_config,
+                    Err(e) => {
+                        log::error!("Failed to load the source config file: {}", e);
+
+                        process::exit(1);
+                    }
+                },
+                None => Config::default(),
+            };
+
+            let mut target_config = config;
+
+            if let Some(from) = from {
+                source_config.comments.dialect = from.clone();
+            }
+
+            if let Some(to) = to {
+                target_config.comments.dialect = to.clone();
+            }
+
+            let migrator = Migrator::new(&source_config, &target_config);
+
+            let mut document = load_document(values, &reporter, &diagnostics);
+            let (_, metadata) = parse_chart(&document, &source_config, &reporter, &diagnostics);
+
+            migrator.migrate(&mut document, &metadata, &diagnostics);
+
+            if !document.has_edits() {
+                log::info!("No metadata to migrate");
+            } else if let Err(err) = fs::write(values, document.render()) {
+                exit_with_error(
+                    &reporter,
+                    &diagnostics,
+                    "Failed to write values file",
+                    err.into(),
+                );
+            }
 
-            ()
+            let _ = reporter.report(&diagnostics);
         }
         None => (),
     }
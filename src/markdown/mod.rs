@@ -3,3 +3,4 @@
 * SPDX-License-Identifier: Apache-2.0
 */
 pub mod render;
+pub mod template;
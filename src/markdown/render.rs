@@ -3,117 +3,359 @@
 * SPDX-License-Identifier: Apache-2.0
 */
 
-use crate::config::Config;
+use crate::config::{Config, ModifiersConfig, RenderMode};
+use crate::diagnostics::{Diagnostic, Diagnostics, Rule};
+use crate::helm::values::ChartValues;
+use crate::markdown::template::{ParamView, ParamsTemplate, SectionView, DEFAULT_TEMPLATE};
+use crate::metadata::chart::ChartMetadata;
+use crate::metadata::modifier::Modifier;
+use crate::metadata::value::ValueMetadata;
 use anyhow::Result;
-use markdown_table::{Heading, MarkdownTable};
 use regex::Regex;
+use serde_yaml::Value;
+use std::fs;
 use std::fs::File;
-use std::io;
 use std::io::BufRead;
 use std::io::Write;
 use std::path::PathBuf;
+use std::rc::Rc;
+use std::{fmt, io};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub struct RenderError {
+    message: String,
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl RenderError {
+    pub fn new(msg: String) -> Self {
+        RenderError { message: msg }
+    }
+}
+
+/// MarkerError is raised when marker comments in the markdown file are unbalanced
+#[derive(Error, Debug)]
+pub struct MarkerError {
+    message: String,
+    line: usize,
+}
+
+impl fmt::Display for MarkerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (line {})", self.message, self.line)
+    }
+}
+
+impl MarkerError {
+    pub fn new(msg: String, line: usize) -> Self {
+        MarkerError { message: msg, line }
+    }
+}
 
 pub struct MarkdownRenderer {
+    mode: RenderMode,
     param_section_pattern: Regex,
+    heading_pattern: Regex,
+    start_marker: String,
+    end_marker: String,
+    template: ParamsTemplate,
+    modifiers: ModifiersConfig,
 }
 
 impl MarkdownRenderer {
-    pub fn new(config: &Config) -> MarkdownRenderer {
+    pub fn new(config: &Config) -> Result<MarkdownRenderer> {
         let param_section_pattern = Regex::new(&format!(
             r"^(##+) {}",
             regex::escape(&config.regexp.params_section_title)
         ))
         .unwrap();
+        let heading_pattern = Regex::new(r"^(#+)\s").unwrap();
 
-        MarkdownRenderer {
+        let template_source = match &config.markdown.template {
+            Some(template_path) => {
+                log::debug!(
+                    "Loading the params template: {}",
+                    template_path.to_string_lossy()
+                );
+
+                fs::read_to_string(template_path).map_err(|err| {
+                    RenderError::new(format!(
+                        "failed to read the template {}: {}",
+                        template_path.to_string_lossy(),
+                        err
+                    ))
+                })?
+            }
+            None => DEFAULT_TEMPLATE.to_string(),
+        };
+
+        Ok(MarkdownRenderer {
+            mode: config.markdown.mode.clone(),
             param_section_pattern,
-        }
+            heading_pattern,
+            start_marker: format!("<!-- {} -->", config.markdown.markers.start),
+            end_marker: format!("<!-- {} -->", config.markdown.markers.end),
+            template: ParamsTemplate::new(template_source, config.markdown.type_column),
+            modifiers: config.modifiers.clone(),
+        })
     }
 
     /// Modify the given markdown file (e.g. README.md) to update the parameters section
-    pub fn render(&self, markdown_path: &PathBuf) -> Result<()> {
+    pub fn render(
+        &self,
+        markdown_path: &PathBuf,
+        metadata: &ChartMetadata,
+        values: &ChartValues,
+        diagnostics: &Diagnostics,
+    ) -> Result<()> {
+        log::debug!(
+            "Updating markdown file: {:?}",
+            markdown_path.to_string_lossy()
+        );
+
+        let Some(new_content) =
+            self.render_content(markdown_path, metadata, values, diagnostics)?
+        else {
+            return Ok(());
+        };
+
+        // resave the markdown file
+        let mut new_md_file = File::create(markdown_path)?;
+
+        new_md_file.write_all(new_content.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Render the given markdown file with the updated parameters section in memory.
+    /// Returns None if there is no place for the parameters section in the file
+    pub fn render_content(
+        &self,
+        markdown_path: &PathBuf,
+        metadata: &ChartMetadata,
+        values: &ChartValues,
+        diagnostics: &Diagnostics,
+    ) -> Result<Option<String>> {
         let md_file = File::open(markdown_path)?;
         let reader = io::BufReader::new(md_file);
 
+        let new_content = match self.mode {
+            RenderMode::Heading => self.replace_params_section(reader, metadata, values)?,
+            RenderMode::Markers => self
+                .replace_marked_region(reader, metadata, values)
+                .map_err(|err| match err.downcast::<MarkerError>() {
+                    Ok(marker_err) => diagnostics
+                        .report_failure(
+                            Diagnostic::error(
+                                Rule::UnbalancedMarkers,
+                                markdown_path,
+                                marker_err.message,
+                            )
+                            .with_line(marker_err.line),
+                        )
+                        .into(),
+                    Err(err) => err,
+                })?,
+        };
+
+        if new_content.is_none() {
+            let message = match self.mode {
+                RenderMode::Heading => {
+                    "The parameters section was not found in the markdown file. \
+                    No parameters table will be rendered"
+                        .to_string()
+                }
+                RenderMode::Markers => format!(
+                    "The {} and {} markers were not found in the markdown file. \
+                    No parameters table will be rendered",
+                    self.start_marker, self.end_marker
+                ),
+            };
+
+            diagnostics.report(Diagnostic::warning(
+                Rule::ParamsSectionNotFound,
+                markdown_path,
+                message,
+            ));
+        }
+
+        Ok(new_content.map(|lines| {
+            lines
+                .into_iter()
+                .map(|line| format!("{}\n", line))
+                .collect()
+        }))
+    }
+
+    /// Replace everything under the parameters section heading up to the next heading of the same level
+    fn replace_params_section<R: BufRead>(
+        &self,
+        reader: R,
+        metadata: &ChartMetadata,
+        values: &ChartValues,
+    ) -> Result<Option<Vec<String>>> {
         let mut new_content = Vec::with_capacity(100);
 
         let mut param_section_level: Option<String> = None;
         let mut next_section_pattern: Option<Regex> = None;
         let mut next_section_found = false;
 
-        log::debug!(
-            "Updating markdown file: {:?}",
-            markdown_path.to_string_lossy()
-        );
-
         for (line_idx, read_res) in reader.lines().enumerate() {
-            match read_res {
-                Ok(line) => {
-                    if let Some(section_level) = self.try_find_param_section(&line).take() {
+            let line = read_res?;
+
+            if let Some(section_level) = self.try_find_param_section(&line) {
+                log::debug!(
+                    "The parameters section is found at line {} (level: {})",
+                    line_idx + 1,
+                    section_level
+                );
+
+                next_section_pattern = Some(Regex::new(&format!(
+                    r"^{}\s",
+                    regex::escape(&section_level.clone())
+                ))?);
+
+                let params = self.render_params(&section_level, metadata, values)?;
+
+                param_section_level = Some(section_level);
+
+                new_content.push(format!("{}\n", line));
+                new_content.push(params);
+
+                continue;
+            }
+
+            if param_section_level.is_some() && !next_section_found {
+                if let Some(section_pattern) = next_section_pattern.as_ref() {
+                    if section_pattern.is_match(&line) {
+                        next_section_found = true;
+
+                        log::debug!("The next section is found at line {}", line_idx + 1,);
+                    } else {
                         log::debug!(
-                            "The parameters section is found at line {} (level: {})",
+                            "Skip line {} (the old parameters section): {}",
                             line_idx + 1,
-                            section_level
+                            line
                         );
+                        continue;
+                    }
+                }
+            }
 
-                        next_section_pattern = Some(Regex::new(&format!(
-                            r"^{}\s",
-                            regex::escape(&section_level.clone())
-                        ))?);
+            log::debug!("Keep line {}: {}", line_idx + 1, line);
+            new_content.push(line);
+        }
 
-                        param_section_level = Some(section_level);
+        if param_section_level.is_none() {
+            return Ok(None);
+        }
 
-                        let param_table = self.render_params()?;
+        Ok(Some(new_content))
+    }
 
-                        new_content.push(format!("{}\n", line));
-                        new_content.push(param_table.to_string());
+    /// Replace only the content between the start and end marker comments,
+    /// so the rest of the parameters section stays untouched
+    fn replace_marked_region<R: BufRead>(
+        &self,
+        reader: R,
+        metadata: &ChartMetadata,
+        values: &ChartValues,
+    ) -> Result<Option<Vec<String>>> {
+        let mut new_content = Vec::with_capacity(100);
 
-                        continue;
-                    }
+        // section headings are rendered one level below the closest heading above the start marker
+        let mut heading_level = "#".to_string();
+        let mut start_line: Option<usize> = None;
+        let mut region_found = false;
 
-                    if param_section_level.is_some() && !next_section_found {
-                        if let Some(section_pattern) = next_section_pattern.as_ref() {
-                            if section_pattern.is_match(&line) {
-                                next_section_found = true;
-
-                                log::debug!("The next section is found at line {}", line_idx + 1,);
-                            } else {
-                                log::debug!(
-                                    "Skip line {} (the old parameters section): {}",
-                                    line_idx + 1,
-                                    line
-                                );
-                                continue;
-                            }
-                        }
-                    }
+        for (line_idx, read_res) in reader.lines().enumerate() {
+            let line = read_res?;
 
-                    log::debug!("Keep line {}: {}", line_idx + 1, line);
-                    new_content.push(line);
+            if line.trim() == self.start_marker {
+                if let Some(start_idx) = start_line {
+                    return Err(MarkerError::new(
+                        format!(
+                            "The {} marker at line {} is not closed before the next one",
+                            self.start_marker,
+                            start_idx + 1,
+                        ),
+                        line_idx + 1,
+                    )
+                    .into());
                 }
-                Err(_err) => {
-                    todo!()
+
+                log::debug!("The start marker is found at line {}", line_idx + 1);
+
+                start_line = Some(line_idx);
+                region_found = true;
+
+                let params = self.render_params(&heading_level, metadata, values)?;
+
+                new_content.push(format!("{}\n", line));
+                new_content.push(params);
+
+                continue;
+            }
+
+            if line.trim() == self.end_marker {
+                if start_line.is_none() {
+                    return Err(MarkerError::new(
+                        format!(
+                            "The {} marker has no matching {} marker",
+                            self.end_marker, self.start_marker
+                        ),
+                        line_idx + 1,
+                    )
+                    .into());
                 }
+
+                log::debug!("The end marker is found at line {}", line_idx + 1);
+
+                start_line = None;
+                new_content.push(line);
+
+                continue;
             }
-        }
 
-        if param_section_level.is_none() {
-            log::warn!(
-                "The parameters section was not found in the markdown file. \
-                No parameters table will be rendered"
-            );
+            if start_line.is_some() {
+                log::debug!(
+                    "Skip line {} (the old marked region): {}",
+                    line_idx + 1,
+                    line
+                );
+                continue;
+            }
 
-            return Ok(());
+            if let Some(captures) = self.heading_pattern.captures(&line) {
+                heading_level = captures[1].to_string();
+            }
+
+            log::debug!("Keep line {}: {}", line_idx + 1, line);
+            new_content.push(line);
         }
 
-        // resave the markdown file
-        let mut new_md_file = File::create(markdown_path)?;
+        if let Some(start_idx) = start_line {
+            return Err(MarkerError::new(
+                format!(
+                    "The {} marker has no matching {} marker",
+                    self.start_marker, self.end_marker
+                ),
+                start_idx + 1,
+            )
+            .into());
+        }
 
-        for line in new_content {
-            writeln!(new_md_file, "{}", line)?;
+        if !region_found {
+            return Ok(None);
         }
 
-        Ok(())
+        Ok(Some(new_content))
     }
 
     fn try_find_param_section(&self, line: &str) -> Option<String> {
@@ -124,19 +366,283 @@ impl MarkdownRenderer {
         None
     }
 
-    fn render_params(&self) -> Result<String> {
-        let mut param_table = MarkdownTable::new(vec![vec![
-            "`autoscaling.enabled`".to_string(),
-            "Enable autoscaling for replicas (recommended if load is variable)".to_string(),
-            "`false`".to_string(),
-        ]]);
+    /// Render parameter tables grouped by sections
+    /// (section headings are placed one level below the parameters section heading)
+    fn render_params(
+        &self,
+        section_level: &str,
+        metadata: &ChartMetadata,
+        values: &ChartValues,
+    ) -> Result<String> {
+        let mut sections = Vec::with_capacity(metadata.sections().len() + 1);
+
+        let unsectioned_params = self.build_params(&metadata.unsectioned_values(), values);
+
+        if !unsectioned_params.is_empty() {
+            sections.push(SectionView {
+                name: None,
+                description: "".to_string(),
+                params: unsectioned_params,
+            });
+        }
+
+        for section in metadata.sections() {
+            sections.push(SectionView {
+                name: Some(section.name().to_string()),
+                description: section.descr().join("\n").trim_end().to_string(),
+                params: self.build_params(&section.values(), values),
+            });
+        }
 
-        param_table.with_headings(vec![
-            Heading::new("Name".to_string(), None),
-            Heading::new("Description".to_string(), None),
-            Heading::new("Value".to_string(), None),
-        ]);
+        self.template
+            .render(&format!("{}#", section_level), &sections)
+    }
+
+    fn build_params(&self, params: &[Rc<ValueMetadata>], values: &ChartValues) -> Vec<ParamView> {
+        let mut param_views = Vec::with_capacity(params.len());
+
+        for param in params {
+            if param.has_skipped() {
+                continue;
+            }
+
+            // every field ends up in a table cell, so pipes are escaped not to break the row
+            param_views.push(ParamView {
+                name: escape_cell(param.name()),
+                description: escape_cell(&param.descr().unwrap_or_default()),
+                value: self
+                    .param_value(param, values)
+                    .map(|value| escape_cell(&value)),
+                param_type: param.param_type().map(escape_cell),
+                modifiers: param
+                    .modifiers()
+                    .iter()
+                    .map(|modifier| escape_cell(&modifier.format(&self.modifiers)))
+                    .collect(),
+                allowed_values: self
+                    .allowed_values(param)
+                    .map(|allowed_values| escape_cell(&allowed_values)),
+                required: param.is_required(),
+            });
+        }
+
+        param_views
+    }
+
+    /// The default value of the param as it's shown in the parameters table
+    fn param_value(&self, param: &ValueMetadata, values: &ChartValues) -> Option<String> {
+        // the default value overridden in the metadata is shown as is
+        if let Some(default) = param.default() {
+            return Some(default.to_string());
+        }
 
-        param_table.as_markdown()
+        let value = if param.is_subtree() {
+            values.subtree(param.name())
+        } else {
+            values.get(param.name())
+        };
+
+        if value.is_none() && !param.has_extra() {
+            log::debug!("The {} param is not found in the values file", param.name());
+        }
+
+        match value {
+            Some(Value::Null) if param.modifiers().contains(&Modifier::Nullable) => {
+                Some("nil".to_string())
+            }
+            // the declared type is shown as its empty value whatever the value is (e.g. [] for [array])
+            value => self
+                .empty_value(param)
+                .or_else(|| value.map(|value| self.format_value(&value))),
+        }
+    }
+
+    /// Describe constraints of the param the way they are shown in the parameters table
+    fn allowed_values(&self, param: &ValueMetadata) -> Option<String> {
+        let modifiers = param.modifiers();
+        let mut constraints = Vec::new();
+
+        for modifier in modifiers {
+            match modifier {
+                Modifier::Enum(values) => constraints.push(
+                    values
+                        .iter()
+                        .map(|value| format!("`{}`", value))
+                        .collect::<Vec<String>>()
+                        .join(", "),
+                ),
+                Modifier::Pattern(pattern) => constraints.push(format!("matches `{}`", pattern)),
+                _ => {}
+            }
+        }
+
+        let min = modifiers.iter().find_map(|m| match m {
+            Modifier::Min(min) => Some(min),
+            _ => None,
+        });
+        let max = modifiers.iter().find_map(|m| match m {
+            Modifier::Max(max) => Some(max),
+            _ => None,
+        });
+
+        match (min, max) {
+            (Some(min), Some(max)) => constraints.push(format!("from `{}` to `{}`", min, max)),
+            (Some(min), None) => constraints.push(format!("at least `{}`", min)),
+            (None, Some(max)) => constraints.push(format!("at most `{}`", max)),
+            (None, None) => {}
+        }
+
+        if constraints.is_empty() {
+            return None;
+        }
+
+        Some(constraints.join("; "))
+    }
+
+    /// The empty value as it's shown for the declared type (e.g. [] for arrays)
+    fn empty_value(&self, param: &ValueMetadata) -> Option<String> {
+        let empty_value = match param.modifiers().iter().find(|m| m.param_type().is_some()) {
+            Some(Modifier::Array) => "[]",
+            Some(Modifier::Object) => "{}",
+            Some(Modifier::String) => "\"\"",
+            _ => return None,
+        };
+
+        Some(empty_value.to_string())
+    }
+
+    /// Format a default value the way it's shown in the parameters table
+    fn format_value(&self, value: &Value) -> String {
+        match value {
+            Value::Null => "nil".to_string(),
+            Value::Bool(value) => value.to_string(),
+            Value::Number(value) => value.to_string(),
+            Value::String(value) if value.is_empty() => "\"\"".to_string(),
+            Value::String(value) => value.to_string(),
+            Value::Sequence(_) | Value::Mapping(_) => {
+                serde_json::to_string(value).unwrap_or_default()
+            }
+            Value::Tagged(tagged) => self.format_value(&tagged.value),
+        }
+    }
+}
+
+/// Escape the text that is placed into a markdown table cell
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_cells_escape_pipes() {
+        let renderer = MarkdownRenderer::new(&Config::default()).unwrap();
+        let param = ValueMetadata::new(
+            "policy".to_string(),
+            vec![
+                Modifier::Enum(vec!["a|b".to_string(), "c".to_string()]),
+                Modifier::Pattern("^(a|b)$".to_string()),
+            ],
+            Some("Either a|b or c".to_string()),
+        );
+        let values = ChartValues::new();
+        values.insert("policy".to_string(), "a|b".into());
+
+        let views = renderer.build_params(&[Rc::new(param)], &values);
+
+        assert_eq!(views[0].description, "Either a\\|b or c");
+        assert_eq!(views[0].value, Some("a\\|b".to_string()));
+        assert_eq!(
+            views[0].allowed_values,
+            Some("`a\\|b`, `c`; matches `^(a\\|b)$`".to_string())
+        );
+    }
+
+    /// The line the marker error is reported at (the markers are written as %start% and %end%)
+    fn marker_error_line(markdown: &str) -> usize {
+        let renderer = MarkdownRenderer::new(&Config::default()).unwrap();
+        let markdown = markdown
+            .replace("%start%", &renderer.start_marker)
+            .replace("%end%", &renderer.end_marker);
+
+        let err = renderer
+            .replace_marked_region(
+                std::io::Cursor::new(markdown),
+                &ChartMetadata::new(),
+                &ChartValues::new(),
+            )
+            .unwrap_err();
+
+        err.downcast::<MarkerError>().unwrap().line
+    }
+
+    #[test]
+    fn start_marker_without_end() {
+        assert_eq!(marker_error_line("# Chart\n\n%start%\n| old |\n"), 3);
+    }
+
+    #[test]
+    fn end_marker_without_start() {
+        assert_eq!(marker_error_line("# Chart\n| old |\n%end%\n"), 3);
+    }
+
+    #[test]
+    fn start_marker_repeated_before_end() {
+        assert_eq!(
+            marker_error_line("# Chart\n%start%\n| old |\n%start%\n%end%\n"),
+            4
+        );
+    }
+
+    fn param_value(modifiers: Vec<Modifier>, value: Option<Value>) -> Option<String> {
+        let renderer = MarkdownRenderer::new(&Config::default()).unwrap();
+        let param = ValueMetadata::new("param".to_string(), modifiers, None);
+        let values = ChartValues::new();
+
+        if let Some(value) = value {
+            values.insert("param".to_string(), value);
+        }
+
+        renderer.param_value(&param, &values)
+    }
+
+    #[test]
+    fn type_modifier_shows_empty_value() {
+        let labels: Value = serde_yaml::from_str("app: web").unwrap();
+
+        assert_eq!(
+            param_value(vec![Modifier::Object], Some(labels)),
+            Some("{}".to_string())
+        );
+        assert_eq!(
+            param_value(vec![Modifier::Object], None),
+            Some("{}".to_string())
+        );
+        assert_eq!(
+            param_value(vec![Modifier::Array], Some(Value::Null)),
+            Some("[]".to_string())
+        );
+        assert_eq!(
+            param_value(vec![Modifier::String], Some("secret".into())),
+            Some("\"\"".to_string())
+        );
+    }
+
+    #[test]
+    fn nullable_null_shows_nil() {
+        assert_eq!(
+            param_value(
+                vec![Modifier::String, Modifier::Nullable],
+                Some(Value::Null)
+            ),
+            Some("nil".to_string())
+        );
+        assert_eq!(
+            param_value(vec![], Some(Value::Null)),
+            Some("nil".to_string())
+        );
+        assert_eq!(param_value(vec![], Some(1.into())), Some("1".to_string()));
     }
 }
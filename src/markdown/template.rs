@@ -0,0 +1,143 @@
+/*
+* Copyright 2024, Roma Hlushko
+* SPDX-License-Identifier: Apache-2.0
+*/
+
+use anyhow::Result;
+use markdown_table::{Heading, MarkdownTable};
+use minijinja::value::ViaDeserialize;
+use minijinja::{context, Environment, Error, ErrorKind};
+use serde::{Deserialize, Serialize};
+
+/// The built-in template that renders one table per section
+pub const DEFAULT_TEMPLATE: &str = include_str!("templates/params.md.jinja");
+
+/// ParamView is a documented chart value as it's exposed to templates
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParamView {
+    pub name: String,
+    pub description: String,
+    /// The default value formatted for markdown (e.g. "redis", "nil", "[]")
+    pub value: Option<String>,
+    #[serde(rename = "type")]
+    pub param_type: Option<String>,
+    pub modifiers: Vec<String>,
+    /// Constraints of the param formatted for markdown (e.g. "`Always`, `Never`")
+    pub allowed_values: Option<String>,
+    /// The value must be provided by the chart user
+    pub required: bool,
+}
+
+/// SectionView is a group of params as it's exposed to templates.
+/// Params defined before the first section are grouped into a section without a name
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SectionView {
+    pub name: Option<String>,
+    pub description: String,
+    pub params: Vec<ParamView>,
+}
+
+/// ParamsTemplate renders the parameters region of the markdown file
+pub struct ParamsTemplate {
+    env: Environment<'static>,
+    source: String,
+    type_column: bool,
+}
+
+impl ParamsTemplate {
+    pub fn new(source: String, type_column: bool) -> ParamsTemplate {
+        let mut env = Environment::new();
+
+        env.set_trim_blocks(true);
+        env.set_lstrip_blocks(true);
+        env.add_filter("params_table", params_table);
+
+        ParamsTemplate {
+            env,
+            source,
+            type_column,
+        }
+    }
+
+    /// Render the template. The heading is the markdown heading prefix that should be used for section titles
+    pub fn render(&self, heading: &str, sections: &[SectionView]) -> Result<String> {
+        let rendered = self.env.render_str(
+            &self.source,
+            context! {
+                heading => heading,
+                sections => sections,
+                type_column => self.type_column,
+            },
+        )?;
+
+        Ok(format!("{}\n", rendered.trim_end_matches('\n')))
+    }
+}
+
+/// Render the given params as a markdown table.
+/// The required and allowed values columns are added only if some of the params need them,
+/// the type column is added on demand (e.g. params_table(true))
+fn params_table(
+    params: ViaDeserialize<Vec<ParamView>>,
+    type_column: Option<bool>,
+) -> Result<String, Error> {
+    let with_type = type_column.unwrap_or_default();
+    let with_required = params.iter().any(|param| param.required);
+    let with_allowed_values = params.iter().any(|param| param.allowed_values.is_some());
+
+    let rows = params
+        .iter()
+        .map(|param| {
+            let mut row = vec![format!("`{}`", param.name), param.description.clone()];
+
+            if with_type {
+                row.push(param.param_type.clone().unwrap_or_default());
+            }
+
+            row.push(
+                param
+                    .value
+                    .as_ref()
+                    .map(|value| format!("`{}`", value))
+                    .unwrap_or_default(),
+            );
+
+            if with_required {
+                row.push(if param.required { "yes" } else { "" }.to_string());
+            }
+
+            if with_allowed_values {
+                row.push(param.allowed_values.clone().unwrap_or_default());
+            }
+
+            row
+        })
+        .collect();
+
+    let mut param_table = MarkdownTable::new(rows);
+
+    let mut headings = vec![
+        Heading::new("Name".to_string(), None),
+        Heading::new("Description".to_string(), None),
+    ];
+
+    if with_type {
+        headings.push(Heading::new("Type".to_string(), None));
+    }
+
+    headings.push(Heading::new("Value".to_string(), None));
+
+    if with_required {
+        headings.push(Heading::new("Required".to_string(), None));
+    }
+
+    if with_allowed_values {
+        headings.push(Heading::new("Allowed values".to_string(), None));
+    }
+
+    param_table.with_headings(headings);
+
+    param_table
+        .as_markdown()
+        .map_err(|err| Error::new(ErrorKind::InvalidOperation, err.to_string()))
+}
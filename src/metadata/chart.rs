@@ -29,4 +29,21 @@ impl ChartMetadata {
     pub fn add_value(&mut self, chat_value: Rc<ValueMetadata>) {
         self.values.push(chat_value)
     }
+
+    pub fn sections(&self) -> &[Rc<SectionMetadata>] {
+        &self.sections
+    }
+
+    pub fn values(&self) -> &[Rc<ValueMetadata>] {
+        &self.values
+    }
+
+    /// Values that were defined before the first section
+    pub fn unsectioned_values(&self) -> Vec<Rc<ValueMetadata>> {
+        self.values
+            .iter()
+            .filter(|value| !self.sections.iter().any(|section| section.has_value(value)))
+            .cloned()
+            .collect()
+    }
 }
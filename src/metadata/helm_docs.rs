@@ -0,0 +1,376 @@
+/*
+* Copyright 2024, Roma Hlushko
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! helm-docs comment dialect (https://github.com/norwoodj/helm-docs):
+//!
+//! ```yaml
+//! # -- (string) Image repository
+//! # @default -- the chart appVersion
+//! # @section -- Image
+//! repository: redis
+//! ```
+
+use crate::diagnostics::{Diagnostic, Diagnostics, Rule};
+use crate::helm::document::ValuesDocument;
+use crate::helm::locator::LineToken;
+use crate::helm::path;
+use crate::helm::values::ChartValues;
+use crate::metadata::chart::ChartMetadata;
+use crate::metadata::modifier::Modifier;
+use crate::metadata::section::SectionMetadata;
+use crate::metadata::value::ValueMetadata;
+use crate::span::Span;
+use regex::Regex;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Annotations collected for the key that follows them
+#[derive(Default)]
+struct PendingParam {
+    name: Option<String>,
+    param_type: Option<String>,
+    descr: Option<String>,
+    /// Plain comments that continue the description
+    descr_lines: Vec<String>,
+    default: Option<String>,
+    section: Option<(String, Span)>,
+    ignored: bool,
+    /// The position of all annotation lines
+    span: Option<Span>,
+}
+
+impl PendingParam {
+    fn extend_span(&mut self, span: Span) {
+        self.span = match self.span.take() {
+            Some(block_span) => Some(block_span.to(&span)),
+            None => Some(span),
+        };
+    }
+
+    fn is_empty(&self) -> bool {
+        self.descr.is_none() && !self.ignored
+    }
+}
+
+/// Metadata collected from the values file so far
+struct CollectedMetadata {
+    metadata: ChartMetadata,
+    sections: HashMap<String, Rc<SectionMetadata>>,
+    /// The line each value is documented at
+    param_lines: HashMap<String, usize>,
+}
+
+/// HelmDocsParser reads helm-docs annotations into the chart metadata
+pub struct HelmDocsParser {
+    descr_regex: Regex,
+    key_descr_regex: Regex,
+    default_regex: Regex,
+    section_regex: Regex,
+    ignored_regex: Regex,
+    comment_regex: Regex,
+}
+
+impl HelmDocsParser {
+    pub fn new() -> HelmDocsParser {
+        HelmDocsParser {
+            descr_regex: Regex::new(r"^\s*#\s*--\s*(?:\((\w+)\)\s*)?(.*)$").unwrap(),
+            key_descr_regex: Regex::new(r"^\s*#\s*([^\s#]+)\s+--\s*(?:\((\w+)\)\s*)?(.*)$")
+                .unwrap(),
+            default_regex: Regex::new(r"^\s*#\s*@default\s*--\s*(.*)$").unwrap(),
+            section_regex: Regex::new(r"^\s*#\s*@section\s*--\s*(.*)$").unwrap(),
+            ignored_regex: Regex::new(r"^\s*#\s*(?:--\s*)?@ignored\s*$").unwrap(),
+            comment_regex: Regex::new(r"^\s*#\s?(.*)$").unwrap(),
+        }
+    }
+
+    pub fn parse(
+        &self,
+        document: &ValuesDocument,
+        values: &ChartValues,
+        diagnostics: &Diagnostics,
+    ) -> ChartMetadata {
+        let mut collected = CollectedMetadata {
+            metadata: ChartMetadata::new(),
+            sections: HashMap::new(),
+            param_lines: HashMap::new(),
+        };
+        let mut pending = PendingParam::default();
+
+        for (line_idx, doc_line) in document.lines().iter().enumerate() {
+            match doc_line.token {
+                LineToken::Blank => continue,
+                LineToken::Comment => {}
+                // annotations end at the first content line after them
+                _ => {
+                    let param = std::mem::take(&mut pending);
+
+                    self.add_param(
+                        &mut collected,
+                        param,
+                        line_idx + 1,
+                        document,
+                        values,
+                        diagnostics,
+                    );
+
+                    continue;
+                }
+            }
+
+            let line = doc_line.text();
+            let line = line.as_str();
+            let span = Span::trimmed_line(document.path(), line_idx + 1, line);
+            let trimmed = line.trim();
+
+            if self.ignored_regex.is_match(line) {
+                pending.ignored = true;
+                pending.extend_span(span);
+
+                continue;
+            }
+
+            if let Some(captures) = self.default_regex.captures(line) {
+                pending.default = Some(captures[1].trim().to_string());
+                pending.extend_span(span);
+
+                continue;
+            }
+
+            if let Some(captures) = self.section_regex.captures(line) {
+                pending.section = Some((captures[1].trim().to_string(), span.clone()));
+                pending.extend_span(span);
+
+                continue;
+            }
+
+            if let Some(captures) = self.descr_regex.captures(line) {
+                pending.param_type = captures.get(1).map(|t| t.as_str().to_string());
+                pending.descr = Some(captures[2].trim().to_string());
+                pending.extend_span(span);
+
+                continue;
+            }
+
+            if let Some(captures) = self.key_descr_regex.captures(line) {
+                let name = match path::normalize(&captures[1]) {
+                    Ok(name) => name,
+                    Err(_) => captures[1].to_string(),
+                };
+
+                // consecutive key annotations (e.g. # a -- First, # b -- Second) describe different keys
+                if !pending.is_empty() {
+                    let param = std::mem::take(&mut pending);
+                    let line = param.span.as_ref().map_or(line_idx + 1, |s| s.start_line);
+
+                    self.add_param(&mut collected, param, line, document, values, diagnostics);
+                }
+
+                pending.name = Some(name);
+                pending.param_type = captures.get(2).map(|t| t.as_str().to_string());
+                pending.descr = Some(captures[3].trim().to_string());
+                pending.extend_span(span);
+
+                continue;
+            }
+
+            // separator lines (e.g. ##) carry no text
+            if trimmed.chars().all(|c| c == '#') {
+                continue;
+            }
+
+            if let Some(captures) = self.comment_regex.captures(line) {
+                let descr_line = captures[1].trim();
+
+                // plain comments that follow the description continue it as helm-docs does
+                if pending.descr.is_some() && !descr_line.is_empty() {
+                    pending.descr_lines.push(descr_line.to_string());
+                    pending.extend_span(span);
+                }
+            }
+        }
+
+        // annotations at the end of the file (e.g. # a -- At the end) are not followed by content
+        if let Some(line) = pending.span.as_ref().map(|span| span.start_line) {
+            self.add_param(&mut collected, pending, line, document, values, diagnostics);
+        }
+
+        collected.metadata
+    }
+
+    /// Add the annotated param to the metadata.
+    /// Params with no explicit key belong to the key at the given line
+    fn add_param(
+        &self,
+        collected: &mut CollectedMetadata,
+        param: PendingParam,
+        line: usize,
+        document: &ValuesDocument,
+        values: &ChartValues,
+        diagnostics: &Diagnostics,
+    ) {
+        if param.is_empty() {
+            return;
+        }
+
+        let Some(param_span) = param.span.clone() else {
+            return;
+        };
+
+        // annotations belong to the key right below them
+        let name = match param
+            .name
+            .or_else(|| document.key_at(line).map(|key| key.path.clone()))
+        {
+            Some(name) => name,
+            None => {
+                diagnostics.report(
+                    Diagnostic::error(
+                        Rule::UnresolvedName,
+                        &param_span.file,
+                        "The annotations are not followed by a values key".to_string(),
+                    )
+                    .with_span(&param_span),
+                );
+
+                return;
+            }
+        };
+
+        let mut value = ValueMetadata::new(name, vec![], param.descr);
+
+        value.set_span(param_span.clone());
+
+        for descr_line in &param.descr_lines {
+            value.append_descr(descr_line);
+        }
+
+        if let Some(param_type) = param.param_type {
+            // helm-docs type names are brought to the common ones (e.g. object to map)
+            let param_type = match Modifier::from_param_type(&param_type).param_type() {
+                Some(common_type) => common_type.to_string(),
+                None => param_type,
+            };
+
+            value.set_param_type(param_type);
+        }
+
+        if let Some(default) = values
+            .get(value.name())
+            .or_else(|| values.subtree(value.name()))
+        {
+            value.infer_param_type(&default);
+        }
+
+        if let Some(default) = param.default {
+            value.set_default(default);
+        }
+
+        if param.ignored {
+            value.skip();
+        }
+
+        if let Some(prev_line) = collected.param_lines.get(value.name()) {
+            diagnostics.report(
+                Diagnostic::warning(
+                    Rule::DuplicateParam,
+                    &param_span.file,
+                    format!(
+                        "The {} value is already documented at line {}",
+                        value.name(),
+                        prev_line
+                    ),
+                )
+                .with_span(&param_span),
+            );
+        } else {
+            collected.param_lines.insert(value.name().to_string(), line);
+        }
+
+        let value = Rc::new(value);
+
+        collected.metadata.add_value(Rc::clone(&value));
+
+        if let Some((section_name, section_span)) = param.section {
+            let metadata = &mut collected.metadata;
+            let section = collected
+                .sections
+                .entry(section_name.clone())
+                .or_insert_with(|| {
+                    let section = Rc::new(SectionMetadata::new(section_name, section_span));
+
+                    metadata.add_section(Rc::clone(&section));
+
+                    section
+                });
+
+            section.add_value(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{CommentDialect, Config};
+    use crate::diagnostics::Rule;
+    use crate::testing::{parse_chart, ParsedChart};
+
+    fn parse(content: &str) -> ParsedChart {
+        let mut config = Config::default();
+
+        config.comments.dialect = CommentDialect::HelmDocs;
+
+        parse_chart(content, &config)
+    }
+
+    #[test]
+    fn annotation_at_the_end_of_file() {
+        let chart = parse("a: 1\n# a -- At the end\n");
+
+        assert_eq!(chart.param_names(), vec!["a"]);
+        assert_eq!(
+            chart.metadata.values()[0].descr().as_deref(),
+            Some("At the end")
+        );
+        assert!(chart.rules().is_empty());
+    }
+
+    #[test]
+    fn unnamed_annotation_at_the_end_of_file() {
+        let chart = parse("a: 1\n# -- Belongs to nothing\n");
+
+        assert!(chart.param_names().is_empty());
+        assert_eq!(chart.rules(), vec![Rule::UnresolvedName]);
+    }
+
+    #[test]
+    fn consecutive_key_annotations() {
+        let chart = parse("# a -- First\n# b.c -- Second\na: 1\nb:\n  c: 2\n");
+
+        assert_eq!(chart.param_names(), vec!["a", "b.c"]);
+        assert!(chart.rules().is_empty());
+    }
+
+    #[test]
+    fn description_continues_on_plain_comments() {
+        let chart = parse("# -- (object) Resources\n# spanning two lines\nresources: {}\n");
+        let param = &chart.metadata.values()[0];
+
+        assert_eq!(param.name(), "resources");
+        assert_eq!(
+            param.descr().as_deref(),
+            Some("Resources spanning two lines")
+        );
+        assert_eq!(param.param_type(), Some("map"));
+    }
+
+    #[test]
+    fn ignored_values_are_skipped() {
+        let chart = parse("# @ignored\ninternal: true\n# -- Replicas\nreplicas: 1\n");
+        let values = chart.metadata.values();
+
+        assert_eq!(chart.param_names(), vec!["internal", "replicas"]);
+        assert!(values[0].has_skipped());
+        assert!(!values[1].has_skipped());
+    }
+}
@@ -4,6 +4,9 @@
 */
 
 pub mod chart;
+pub mod helm_docs;
+pub mod modifier;
 pub mod parser;
 pub mod section;
 pub mod value;
+pub mod writer;
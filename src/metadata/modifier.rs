@@ -0,0 +1,183 @@
+/*
+* Copyright 2024, Roma Hlushko
+* SPDX-License-Identifier: Apache-2.0
+*/
+
+use crate::config::ModifiersConfig;
+use regex::Regex;
+use serde_yaml::Value;
+
+/// The separator of allowed values (e.g. [enum: Always|IfNotPresent|Never])
+const ENUM_SEPARATOR: char = '|';
+
+/// Modifier changes how the param is documented (e.g. ## @param tolerations [array] Pod tolerations)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Modifier {
+    Array,
+    Object,
+    String,
+    Nullable,
+    Subtree,
+    /// The value must be provided by the chart user
+    Required,
+    /// Overrides the shown default value (e.g. [default: the chart appVersion])
+    Default(String),
+    /// Values the param is allowed to have (e.g. [enum: Always|IfNotPresent|Never])
+    Enum(Vec<String>),
+    /// The lowest allowed value (e.g. [min: 1])
+    Min(f64),
+    /// The highest allowed value (e.g. [max: 10])
+    Max(f64),
+    /// The regular expression the value should match (e.g. [pattern: ^v\d+])
+    Pattern(String),
+    /// A modifier that is not known to chartpedia
+    Other(String),
+}
+
+impl Modifier {
+    /// Parse the modifier as it's named in the given config
+    pub fn parse(modifier: &str, config: &ModifiersConfig) -> Modifier {
+        let modifier = modifier.trim();
+
+        if let Some(default) = modifier_arg(modifier, &config.default) {
+            return Modifier::Default(default.to_string());
+        }
+
+        if let Some(values) = modifier_arg(modifier, &config.r#enum) {
+            return Modifier::Enum(
+                values
+                    .split(ENUM_SEPARATOR)
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+                    .collect(),
+            );
+        }
+
+        if let Some(min) = modifier_arg(modifier, &config.min) {
+            return match min.parse() {
+                Ok(min) => Modifier::Min(min),
+                Err(_) => Modifier::Other(modifier.to_string()),
+            };
+        }
+
+        if let Some(max) = modifier_arg(modifier, &config.max) {
+            return match max.parse() {
+                Ok(max) => Modifier::Max(max),
+                Err(_) => Modifier::Other(modifier.to_string()),
+            };
+        }
+
+        if let Some(pattern) = modifier_arg(modifier, &config.pattern) {
+            return Modifier::Pattern(pattern.to_string());
+        }
+
+        match modifier {
+            m if m == config.array => Modifier::Array,
+            m if m == config.object => Modifier::Object,
+            m if m == config.string => Modifier::String,
+            m if m == config.nullable => Modifier::Nullable,
+            m if m == config.subtree => Modifier::Subtree,
+            m if m == config.required => Modifier::Required,
+            m => Modifier::Other(m.to_string()),
+        }
+    }
+
+    /// Format the modifier as it's named in the given config
+    pub fn format(&self, config: &ModifiersConfig) -> String {
+        match self {
+            Modifier::Array => config.array.clone(),
+            Modifier::Object => config.object.clone(),
+            Modifier::String => config.string.clone(),
+            Modifier::Nullable => config.nullable.clone(),
+            Modifier::Subtree => config.subtree.clone(),
+            Modifier::Required => config.required.clone(),
+            Modifier::Default(value) => format!("{}: {}", config.default, value),
+            Modifier::Enum(values) => format!(
+                "{}: {}",
+                config.r#enum,
+                values.join(&ENUM_SEPARATOR.to_string())
+            ),
+            Modifier::Min(min) => format!("{}: {}", config.min, min),
+            Modifier::Max(max) => format!("{}: {}", config.max, max),
+            Modifier::Pattern(pattern) => format!("{}: {}", config.pattern, pattern),
+            Modifier::Other(modifier) => modifier.clone(),
+        }
+    }
+
+    /// Whether the modifier restricts values the param can have
+    pub fn is_constraint(&self) -> bool {
+        matches!(
+            self,
+            Modifier::Enum(_) | Modifier::Min(_) | Modifier::Max(_) | Modifier::Pattern(_)
+        )
+    }
+
+    /// Check the value against the constraint modifier.
+    /// Returns the reason if the value doesn't satisfy it
+    pub fn violation(&self, value: &Value) -> Option<String> {
+        let value = match value {
+            Value::Tagged(tagged) => &tagged.value,
+            value => value,
+        };
+
+        let scalar = match value {
+            Value::Null => return None,
+            Value::Bool(value) => value.to_string(),
+            Value::Number(value) => value.to_string(),
+            Value::String(value) => value.clone(),
+            // only scalar values can be constrained
+            _ => return None,
+        };
+
+        match self {
+            Modifier::Enum(values) if !values.contains(&scalar) => {
+                Some(format!("{} is not one of {}", scalar, values.join(", ")))
+            }
+            Modifier::Min(_) | Modifier::Max(_) if value.as_f64().is_none() => {
+                Some(format!("{} is not a number", scalar))
+            }
+            Modifier::Min(min) if value.as_f64().is_some_and(|number| number < *min) => {
+                Some(format!("{} is less than {}", scalar, min))
+            }
+            Modifier::Max(max) if value.as_f64().is_some_and(|number| number > *max) => {
+                Some(format!("{} is greater than {}", scalar, max))
+            }
+            Modifier::Pattern(pattern) => match Regex::new(pattern) {
+                Ok(regex) if !regex.is_match(&scalar) => {
+                    Some(format!("{} doesn't match {}", scalar, pattern))
+                }
+                Ok(_) => None,
+                Err(err) => Some(format!("the {} pattern is invalid: {}", pattern, err)),
+            },
+            _ => None,
+        }
+    }
+
+    /// Map the value type (e.g. list) to the modifier that declares it
+    pub fn from_param_type(param_type: &str) -> Modifier {
+        match param_type {
+            "list" => Modifier::Array,
+            "map" | "object" | "dict" => Modifier::Object,
+            "string" => Modifier::String,
+            other => Modifier::Other(other.to_string()),
+        }
+    }
+
+    /// The value type the modifier declares if there is one
+    pub fn param_type(&self) -> Option<&'static str> {
+        match self {
+            Modifier::Array => Some("list"),
+            Modifier::Object => Some("map"),
+            Modifier::String => Some("string"),
+            _ => None,
+        }
+    }
+}
+
+/// The argument of the modifier with the given name (e.g. 1 for min: 1)
+fn modifier_arg<'a>(modifier: &'a str, name: &str) -> Option<&'a str> {
+    modifier
+        .strip_prefix(name)
+        .and_then(|rest| rest.trim_start().strip_prefix(':'))
+        .map(|arg| arg.trim())
+}
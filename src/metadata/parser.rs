@@ -3,220 +3,611 @@
 * SPDX-License-Identifier: Apache-2.0
 */
 use crate::config;
-use anyhow::Result;
+use crate::diagnostics::{Diagnostic, Diagnostics, Rule};
+use crate::helm::document::ValuesDocument;
+use crate::helm::locator::LineToken;
+use crate::helm::path;
+use crate::helm::values::ChartValues;
 use regex::Regex;
-use std::error::Error;
-use std::fmt::Display;
-use std::fs::File;
-use std::io::BufRead;
-use std::path::Path;
+use std::collections::HashMap;
 use std::rc::Rc;
 use std::str::FromStr;
-use std::{fmt, io};
 
 use crate::metadata::chart::ChartMetadata;
+use crate::metadata::helm_docs::HelmDocsParser;
+use crate::metadata::modifier::Modifier;
 use crate::metadata::section::SectionMetadata;
 use crate::metadata::value::ValueMetadata;
-use config::Config;
+use crate::span::Span;
+use config::{CommentDialect, Config, ModifiersConfig};
+
+/// Value path (e.g. image.tag or podAnnotations["prometheus.io/scrape"])
+const NAME_PATTERN: &str = r#"(?:[^\s\[]|\[(?:"(?:[^"\\]|\\.)*"|'[^']*'|[^\]"']*)\])+"#;
+
+/// Commented-out YAML (e.g. ## ref: https://... or ## - name: foo) that doesn't continue a param description.
+/// Keys start with a lowercase letter, so prose like "Note: ..." still continues it
+const COMMENTED_YAML_PATTERN: &str = r#"^(?:-(?:\s|$)|[a-z][\w.\-/]*:(?:\s|$)|"[^"]*":(?:\s|$))"#;
+
+/// The param name placeholder that is replaced with the path of the following key (e.g. ## @param - Image tag)
+pub const INFERRED_NAME: &str = "-";
 
 /// MetadataParser parses metadata left in values.yaml file
 pub struct MetadataParser {
     param_regex: Regex,
+    required_regex: Regex,
     section_regex: Regex,
     descr_start_regex: Regex,
     descr_content_regex: Regex,
     descr_end_regex: Regex,
     skip_regex: Regex,
     extra_regex: Regex,
+    commented_yaml_regex: Regex,
+    descr_end_tag: String,
+    modifiers: ModifiersConfig,
+    multiline_descriptions: bool,
+    dialect: CommentDialect,
+    helm_docs_parser: HelmDocsParser,
 }
 
-#[derive(Debug)]
-struct ParsingError {
-    message: String,
+/// DescrState tracks whether description content is being parsed
+#[derive(Debug, PartialEq)]
+enum DescrState {
+    None,
+    /// The description of the current section
+    Section,
+    /// The description that is placed outside of any section
+    Orphan,
 }
 
-impl Display for ParsingError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.message)
-    }
-}
-
-// Implement the std::error::Error trait for your custom error type.
-impl Error for ParsingError {}
-
 impl MetadataParser {
     pub fn new(config: &Config) -> MetadataParser {
         let param_regex = Regex::new(&format!(
-            r"^\\s*{}\\s*{}\\s*([^\\s]+)\\s*(\\[.*?\\])?\\s*(.*)$",
+            r"^\s*{}\s*{}\s*({})\s*(.*)$",
             regex::escape(&config.comments.format),
-            regex::escape(&config.tags.param)
+            regex::escape(&config.tags.param),
+            NAME_PATTERN
+        ))
+        .unwrap();
+        let required_regex = Regex::new(&format!(
+            r"^\s*{}\s*{}\s*({})\s*(.*)$",
+            regex::escape(&config.comments.format),
+            regex::escape(&config.tags.required),
+            NAME_PATTERN
         ))
         .unwrap();
         let section_regex = Regex::new(&format!(
-            r"^\\s*{}\\s*{}\\s*(.*)$",
+            r"^\s*{}\s*{}\s*(.*)$",
             regex::escape(&config.comments.format),
             regex::escape(&config.tags.section)
         ))
         .unwrap();
         let descr_start_regex = Regex::new(&format!(
-            r"^\\s*{}\\s*{}\\s*(.*)",
+            r"^\s*{}\s*{}\s*(.*)",
             regex::escape(&config.comments.format),
             regex::escape(&config.tags.description_start)
         ))
         .unwrap();
         let descr_content_regex = Regex::new(&format!(
-            r"^\\s*{}\\s*(.*)",
+            r"^\s*{}\s*(.*)",
             regex::escape(&config.comments.format)
         ))
         .unwrap();
         let descr_end_regex = Regex::new(&format!(
-            r"^\\s*{}\\s*{}\\s*(.*)",
+            r"^\s*{}\s*{}\s*(.*)",
             regex::escape(&config.comments.format),
             regex::escape(&config.tags.description_end)
         ))
         .unwrap();
         let skip_regex = Regex::new(&format!(
-            r"^\\s*{}\\s*{}\\s*([^\\s]+)\\s*(.*)$",
+            r"^\s*{}\s*{}\s*({})\s*(.*)$",
             regex::escape(&config.comments.format),
-            regex::escape(&config.tags.skip)
+            regex::escape(&config.tags.skip),
+            NAME_PATTERN
         ))
         .unwrap();
         let extra_regex = Regex::new(&format!(
-            r"^\\s*{}\\s*{}\\s*([^\\s]+)\\s*(\\[.*?\\])?\\s*(.*)$",
+            r"^\s*{}\s*{}\s*({})\s*(.*)$",
             regex::escape(&config.comments.format),
-            regex::escape(&config.tags.extra)
+            regex::escape(&config.tags.extra),
+            NAME_PATTERN
         ))
         .unwrap();
 
         MetadataParser {
             param_regex,
+            required_regex,
             section_regex,
             descr_start_regex,
             descr_content_regex,
             descr_end_regex,
             skip_regex,
             extra_regex,
+            commented_yaml_regex: Regex::new(COMMENTED_YAML_PATTERN).unwrap(),
+            descr_end_tag: config.tags.description_end.clone(),
+            modifiers: config.modifiers.clone(),
+            multiline_descriptions: config.comments.multiline_descriptions,
+            dialect: config.comments.dialect.clone(),
+            helm_docs_parser: HelmDocsParser::new(),
         }
     }
 
-    pub fn parse<P: AsRef<Path>>(&self, values_file: P) -> Result<ChartMetadata> {
-        let values_file = File::open(values_file)?;
-        let reader = io::BufReader::new(values_file);
+    /// Parse metadata comments of the document.
+    /// Param names are inferred from the keys placed below the comments
+    pub fn parse(
+        &self,
+        document: &ValuesDocument,
+        values: &ChartValues,
+        diagnostics: &Diagnostics,
+    ) -> ChartMetadata {
+        if self.dialect == CommentDialect::HelmDocs {
+            return self.helm_docs_parser.parse(document, values, diagnostics);
+        }
+
+        let values_path = document.path();
 
         let mut metadata = ChartMetadata::new();
+        let mut param_lines: HashMap<String, usize> = HashMap::new();
         let mut curr_section: Option<Rc<SectionMetadata>> = None;
-        let mut descr_parsing = false;
+        let mut descr_state = DescrState::None;
+        // the param that following plain comment lines are appended to
+        let mut continued_param: Option<Rc<ValueMetadata>> = None;
+
+        for (line_idx, doc_line) in document.lines().iter().enumerate() {
+            let prev_param = continued_param.take();
 
-        for line_res in reader.lines() {
-            match line_res {
-                Ok(line) => {
-                    if let Some(param) = self.try_parse_param(&line) {
-                        let param_rc = Rc::new(param);
+            // metadata lives in full-line comments only (e.g. not in block scalars)
+            if !matches!(doc_line.token, LineToken::Comment) {
+                continue;
+            }
+
+            let line = doc_line.text();
+            let span = Span::trimmed_line(values_path, line_idx + 1, &line);
 
-                        metadata.add_value(Rc::clone(&param_rc));
+            if let Some(mut param) = self.try_parse_param(&line, &span, document, diagnostics) {
+                param.set_span(span.clone());
+
+                if let Some(default) = values
+                    .get(param.name())
+                    .or_else(|| values.subtree(param.name()))
+                {
+                    param.infer_param_type(&default);
+                }
+
+                if let Some(prev_line) = param_lines.get(param.name()) {
+                    diagnostics.report(
+                        Diagnostic::warning(
+                            Rule::DuplicateParam,
+                            values_path,
+                            format!(
+                                "The {} value is already documented at line {}",
+                                param.name(),
+                                prev_line
+                            ),
+                        )
+                        .with_span(&span),
+                    );
+                } else {
+                    param_lines.insert(param.name().to_string(), span.start_line);
+                }
+
+                let param_rc = Rc::new(param);
+
+                metadata.add_value(Rc::clone(&param_rc));
+
+                if self.multiline_descriptions && !param_rc.has_skipped() {
+                    continued_param = Some(Rc::clone(&param_rc));
+                }
+
+                if let Some(section) = &curr_section {
+                    section.add_value(Rc::clone(&param_rc))
+                }
+
+                continue;
+            }
+
+            if let Some(section) = self.try_parse_section(&line, &span) {
+                if descr_state == DescrState::Section {
+                    self.report_unterminated_descr(&curr_section, diagnostics);
+                }
+
+                let section_rc = Rc::new(section);
+                metadata.add_section(Rc::clone(&section_rc));
+
+                curr_section = Some(Rc::clone(&section_rc));
+                descr_state = DescrState::None;
+
+                continue;
+            }
 
+            if self.has_descr_end(&line) {
+                match descr_state {
+                    DescrState::Section => {
                         if let Some(section) = &curr_section {
-                            section.add_value(Rc::clone(&param_rc))
+                            section.extend_descr_span(span);
                         }
                     }
+                    DescrState::Orphan => {}
+                    DescrState::None => diagnostics.report(
+                        Diagnostic::warning(
+                            Rule::OrphanDescription,
+                            values_path,
+                            "The description end has no matching description start".to_string(),
+                        )
+                        .with_span(&span),
+                    ),
+                }
 
-                    if let Some(section) = self.try_parse_section(&line) {
-                        let section_rc = Rc::new(section);
-                        metadata.add_section(Rc::clone(&section_rc));
+                descr_state = DescrState::None;
 
-                        curr_section = Some(Rc::clone(&section_rc))
-                    }
+                continue;
+            }
 
-                    if let Some(has_end) = self.has_descr_end(&line) {
-                        if has_end && curr_section.is_some() && descr_parsing {
-                            descr_parsing = false
+            if let Some(descr_start) = self.try_parse_descr_start(&line) {
+                match (&curr_section, &descr_state) {
+                    (_, DescrState::Section) | (_, DescrState::Orphan) => diagnostics.report(
+                        Diagnostic::warning(
+                            Rule::NestedDescription,
+                            values_path,
+                            "The description start is placed inside of another description"
+                                .to_string(),
+                        )
+                        .with_span(&span),
+                    ),
+                    (Some(section), DescrState::None) => {
+                        descr_state = DescrState::Section;
+
+                        section.extend_descr_span(span);
+
+                        if !descr_start.is_empty() {
+                            section.add_descr(descr_start);
                         }
                     }
-
-                    if let Some(descr_line) = self.try_parse_descr_content(&line) {
-                        match &curr_section {
-                            Some(section) => section.add_descr(descr_line),
-                            None => todo!(),
-                        }
+                    (None, DescrState::None) => {
+                        // the description content is still consumed, so it's not mistaken for other metadata
+                        descr_state = DescrState::Orphan;
+
+                        diagnostics.report(
+                            Diagnostic::warning(
+                                Rule::OrphanDescription,
+                                values_path,
+                                "The description is defined outside of any section".to_string(),
+                            )
+                            .with_span(&span),
+                        );
                     }
+                }
 
-                    if let Some(descr_start) = self.try_parse_descr_start(&line) {
-                        if curr_section.is_some() && !descr_parsing {
-                            descr_parsing = true;
+                continue;
+            }
 
-                            if !descr_start.is_empty() {
-                                if let Some(section) = &curr_section {
-                                    section.add_descr(descr_start);
-                                }
-                            }
-                        }
+            if let Some(param) = prev_param {
+                match self.try_parse_descr_content(&line) {
+                    Some(descr_line)
+                        if !descr_line.trim().is_empty()
+                            && !self.commented_yaml_regex.is_match(descr_line.trim()) =>
+                    {
+                        param.append_descr(descr_line.trim());
+                        param.extend_descr_span(span);
+                        continued_param = Some(param);
+
+                        continue;
                     }
-                }
-                Err(_err) => {
-                    todo!()
+                    // an empty comment line, commented-out YAML or a YAML key ends the description
+                    _ => {}
                 }
             }
+
+            if descr_state != DescrState::Section {
+                continue;
+            }
+
+            if let (Some(descr_line), Some(section)) =
+                (self.try_parse_descr_content(&line), &curr_section)
+            {
+                section.extend_descr_span(span);
+                section.add_descr(descr_line)
+            }
         }
 
-        Ok(metadata)
+        if descr_state == DescrState::Section {
+            self.report_unterminated_descr(&curr_section, diagnostics);
+        }
+
+        metadata
     }
 
-    fn try_parse_param(&self, line: &str) -> Option<ValueMetadata> {
-        if let Some(captures) = self.param_regex.captures(line) {
-            let name = captures[1].to_string();
+    fn report_unterminated_descr(
+        &self,
+        section: &Option<Rc<SectionMetadata>>,
+        diagnostics: &Diagnostics,
+    ) {
+        let Some(descr_span) = section.as_ref().and_then(|section| section.descr_span()) else {
+            return;
+        };
+
+        diagnostics.report(
+            Diagnostic::error(
+                Rule::UnterminatedDescription,
+                &descr_span.file,
+                format!(
+                    "The description start has no matching {} tag",
+                    self.descr_end_tag
+                ),
+            )
+            .with_span(&descr_span),
+        );
+    }
 
-            let modifiers = match captures[2].to_string() {
-                mod_str if !mod_str.is_empty() => mod_str
-                    .trim_matches(|c| c == '[' || c == ']')
-                    .split(',')
-                    .map(|m| m.trim().to_string())
-                    .collect(),
-                _ => vec![],
+    fn try_parse_param(
+        &self,
+        line: &str,
+        span: &Span,
+        document: &ValuesDocument,
+        diagnostics: &Diagnostics,
+    ) -> Option<ValueMetadata> {
+        // the required tag is a shorthand for the param with the required modifier
+        let param_captures = match self.param_regex.captures(line) {
+            Some(captures) => Some((captures, false)),
+            None => self
+                .required_regex
+                .captures(line)
+                .map(|captures| (captures, true)),
+        };
+
+        if let Some((captures, required)) = param_captures {
+            let (name, subtree) = match path::strip_subtree(&captures[1]) {
+                Some(name) => (name, true),
+                None => (&captures[1], false),
             };
+            let inferred = name == INFERRED_NAME;
+            let name = self.parse_name(name, span, document, diagnostics);
+
+            let (mod_str, descr) = self.split_modifiers(&name, &captures[2], span, diagnostics);
 
-            let descr = captures[3].to_string();
+            let modifiers = self.parse_modifiers(&name, mod_str, span, diagnostics);
+            let descr = descr.to_string();
 
-            return Some(ValueMetadata::new(name, modifiers, Some(descr)));
+            let subtree = subtree || modifiers.contains(&Modifier::Subtree);
+
+            let mut param = ValueMetadata::new(name, modifiers, Some(descr));
+
+            if subtree {
+                param.set_subtree();
+            }
+
+            if required {
+                param.set_required();
+            }
+
+            self.apply_modifiers(&mut param);
+
+            if inferred {
+                param.set_inferred_name();
+            }
+
+            return Some(param);
         }
 
         if let Some(captures) = self.skip_regex.captures(line) {
-            let name = captures[1].to_string();
-            let mut param = ValueMetadata::new(name, vec![], None);
+            let name = self.parse_name(&captures[1], span, document, diagnostics);
+            // the reason to skip the value (e.g. ## @skip internal Not for users) is kept for formatting
+            let descr = Some(captures[2].trim().to_string()).filter(|descr| !descr.is_empty());
+            let mut param = ValueMetadata::new(name, vec![], descr);
 
             param.skip();
 
+            if &captures[1] == INFERRED_NAME {
+                param.set_inferred_name();
+            }
+
             return Some(param);
         }
 
         if let Some(captures) = self.extra_regex.captures(line) {
-            let name = String::from_str(&captures[1]).unwrap();
-            let descr = String::from_str(&captures[3]).unwrap();
+            let name = self.parse_name(&captures[1], span, document, diagnostics);
+            let (mod_str, descr) = self.split_modifiers(&name, &captures[2], span, diagnostics);
+            let modifiers = self.parse_modifiers(&name, mod_str, span, diagnostics);
+            let descr = String::from_str(descr).unwrap();
 
-            let mut param = ValueMetadata::new(name, vec![], Some(descr));
+            let mut param = ValueMetadata::new(name, modifiers, Some(descr));
             param.set_extra();
 
+            self.apply_modifiers(&mut param);
+
             return Some(param);
         }
 
         None
     }
 
-    fn try_parse_section(&self, line: &str) -> Option<SectionMetadata> {
-        if let Some(captures) = self.section_regex.captures(line) {
-            return Some(SectionMetadata::new(captures[1].to_string()));
+    /// Split the rest of the param line into the modifier list and the description.
+    /// The unclosed modifier list is reported and kept in the description
+    fn split_modifiers<'a>(
+        &self,
+        name: &str,
+        rest: &'a str,
+        span: &Span,
+        diagnostics: &Diagnostics,
+    ) -> (Option<&'a str>, &'a str) {
+        split_modifiers(rest).unwrap_or_else(|| {
+            diagnostics.report(
+                Diagnostic::error(
+                    Rule::MalformedModifiers,
+                    &span.file,
+                    format!("The modifiers of the {} param are not closed with ]", name),
+                )
+                .with_span(span),
+            );
+
+            (None, rest)
+        })
+    }
+
+    /// Parse the bracketed modifier list (e.g. [array, nullable]) in the configured vocabulary
+    fn parse_modifiers(
+        &self,
+        name: &str,
+        mod_str: Option<&str>,
+        span: &Span,
+        diagnostics: &Diagnostics,
+    ) -> Vec<Modifier> {
+        let Some(mod_str) = mod_str else {
+            return vec![];
+        };
+
+        let mut modifiers: Vec<&str> = Vec::new();
+        let mut depth: usize = 0;
+        let mut escaped = false;
+        let mut start = 0;
+
+        for (idx, c) in mod_str.char_indices() {
+            if escaped {
+                escaped = false;
+                continue;
+            }
+
+            match c {
+                '\\' => escaped = true,
+                '[' | '{' | '(' => depth += 1,
+                ']' | '}' | ')' => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => {
+                    let modifier = mod_str[start..idx].trim();
+                    let next_modifier = mod_str[idx + 1..].split(',').next().unwrap_or_default();
+
+                    // commas in the pattern are kept unless another modifier follows them
+                    if self.is_pattern(modifier) && !self.is_modifier(next_modifier) {
+                        continue;
+                    }
+
+                    modifiers.push(modifier);
+                    start = idx + 1;
+                }
+                _ => {}
+            }
         }
 
-        None
+        modifiers.push(mod_str[start..].trim());
+
+        if modifiers.iter().any(|modifier| modifier.is_empty()) {
+            diagnostics.report(
+                Diagnostic::error(
+                    Rule::MalformedModifiers,
+                    &span.file,
+                    format!("The {} param has an empty modifier", name),
+                )
+                .with_span(span),
+            );
+        }
+
+        modifiers
+            .into_iter()
+            .filter(|m| !m.is_empty())
+            .map(|m| Modifier::parse(m, &self.modifiers))
+            .inspect(|modifier| {
+                if let Modifier::Other(modifier) = modifier {
+                    diagnostics.report(
+                        Diagnostic::warning(
+                            Rule::UnknownModifier,
+                            &span.file,
+                            format!("The {} param has an unknown modifier: {}", name, modifier),
+                        )
+                        .with_span(span),
+                    );
+                }
+            })
+            .collect()
+    }
+
+    fn is_pattern(&self, modifier: &str) -> bool {
+        modifier
+            .strip_prefix(self.modifiers.pattern.as_str())
+            .is_some_and(|rest| rest.trim_start().starts_with(':'))
+    }
+
+    fn is_modifier(&self, modifier: &str) -> bool {
+        !matches!(
+            Modifier::parse(modifier.trim(), &self.modifiers),
+            Modifier::Other(_)
+        )
     }
 
-    fn has_descr_end(&self, line: &str) -> Option<bool> {
-        if self.descr_end_regex.captures(line).is_some() {
-            return Some(true);
+    /// Set the declared type, the shown default and the required flag of the param from its modifiers
+    fn apply_modifiers(&self, param: &mut ValueMetadata) {
+        if param.modifiers().contains(&Modifier::Required) {
+            param.set_required();
+        }
+
+        let param_type = param.modifiers().iter().find_map(Modifier::param_type);
+        let default = param.modifiers().iter().find_map(|m| match m {
+            Modifier::Default(value) => Some(value.clone()),
+            _ => None,
+        });
+
+        if let Some(param_type) = param_type {
+            param.set_param_type(param_type.to_string());
+        }
+
+        if let Some(default) = default {
+            param.set_default(default);
+        }
+    }
+
+    /// Bring the value path to its canonical form, so it can be matched against the values file keys.
+    /// The name placeholder is resolved to the path of the key placed right below the metadata
+    fn parse_name(
+        &self,
+        name: &str,
+        span: &Span,
+        document: &ValuesDocument,
+        diagnostics: &Diagnostics,
+    ) -> String {
+        if name == INFERRED_NAME {
+            return match document.key_below(span.start_line) {
+                Some(key) => key.path.clone(),
+                None => {
+                    diagnostics.report(
+                        Diagnostic::error(
+                            Rule::UnresolvedName,
+                            &span.file,
+                            "No key follows the param to take its name from".to_string(),
+                        )
+                        .with_span(span),
+                    );
+
+                    name.to_string()
+                }
+            };
+        }
+
+        match path::normalize(name) {
+            Ok(name) => name,
+            Err(err) => {
+                diagnostics.report(
+                    Diagnostic::error(
+                        Rule::InvalidPath,
+                        &span.file,
+                        format!("The value path is invalid: {}", err),
+                    )
+                    .with_span(span),
+                );
+
+                name.to_string()
+            }
+        }
+    }
+
+    fn try_parse_section(&self, line: &str, span: &Span) -> Option<SectionMetadata> {
+        if let Some(captures) = self.section_regex.captures(line) {
+            return Some(SectionMetadata::new(captures[1].to_string(), span.clone()));
         }
 
         None
     }
 
+    fn has_descr_end(&self, line: &str) -> bool {
+        self.descr_end_regex.is_match(line)
+    }
+
     fn try_parse_descr_content(&self, line: &str) -> Option<String> {
         if let Some(captures) = self.descr_content_regex.captures(line) {
             return Some(captures[1].to_string());
@@ -233,3 +624,193 @@ impl MetadataParser {
         None
     }
 }
+
+/// Split the rest of the param line into the modifier list (without brackets) and the description.
+/// Brackets nested in the list (e.g. [pattern: ^v[0-9]+$]) are balanced.
+/// Returns None if the modifier list is not closed
+fn split_modifiers(rest: &str) -> Option<(Option<&str>, &str)> {
+    let Some(list) = rest.strip_prefix('[') else {
+        return Some((None, rest));
+    };
+
+    let mut depth: usize = 0;
+    let mut escaped = false;
+
+    for (idx, c) in list.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+
+        match c {
+            '\\' => escaped = true,
+            '[' | '{' | '(' => depth += 1,
+            ']' if depth == 0 => return Some((Some(&list[..idx]), list[idx + 1..].trim_start())),
+            ']' | '}' | ')' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::parse_chart;
+
+    #[test]
+    fn unclosed_modifiers_are_reported() {
+        let parser = MetadataParser::new(&Config::default());
+        let span = Span::new("values.yaml", 1, 1, 1, 1);
+
+        for line in [
+            "## @param image [array Broken",
+            "## @extra ext [array Broken",
+        ] {
+            let diagnostics = Diagnostics::new();
+            let param = parser
+                .try_parse_param(
+                    line,
+                    &span,
+                    &ValuesDocument::parse("values.yaml", line),
+                    &diagnostics,
+                )
+                .unwrap();
+
+            assert_eq!(param.descr().as_deref(), Some("[array Broken"));
+            assert!(diagnostics
+                .items()
+                .iter()
+                .any(|diagnostic| diagnostic.rule == Rule::MalformedModifiers));
+        }
+    }
+
+    #[test]
+    fn required_tag_marks_the_param_required() {
+        let chart = parse_chart(
+            "## @required auth.existingSecret [string] Existing secret\nauth:\n  existingSecret: \"\"\n## @param replicas [required] Replica count\nreplicas: 1\n",
+            &Config::default(),
+        );
+        let values = chart.metadata.values();
+
+        assert_eq!(chart.param_names(), vec!["auth.existingSecret", "replicas"]);
+        assert!(values.iter().all(|param| param.is_required()));
+        assert_eq!(values[0].modifiers(), &[Modifier::String]);
+        assert_eq!(values[0].descr().as_deref(), Some("Existing secret"));
+        assert!(chart.rules().is_empty());
+    }
+
+    #[test]
+    fn types_are_inferred_from_defaults() {
+        let chart = parse_chart(
+            "## @param replicas Replica count\nreplicas: 3\n## @param tag [string] Image tag\ntag: 1.0\n## @param labels Pod labels\nlabels: {}\n",
+            &Config::default(),
+        );
+        let types: Vec<Option<&str>> = chart
+            .metadata
+            .values()
+            .iter()
+            .map(|param| param.param_type())
+            .collect();
+
+        assert_eq!(types, vec![Some("int"), Some("string"), Some("map")]);
+    }
+
+    #[test]
+    fn commented_yaml_ends_description() {
+        let commented_yaml = Regex::new(COMMENTED_YAML_PATTERN).unwrap();
+
+        assert!(commented_yaml.is_match("ref: https://kubernetes.io/docs"));
+        assert!(commented_yaml.is_match("- name: foo"));
+        assert!(commented_yaml.is_match("resources:"));
+        assert!(!commented_yaml.is_match("that spans two lines"));
+        assert!(commented_yaml.is_match(r#""app.kubernetes.io/name": web"#));
+        assert!(!commented_yaml.is_match("see the docs: https://kubernetes.io"));
+        assert!(!commented_yaml.is_match("Note: https is required"));
+        assert!(!commented_yaml.is_match("Default: 10"));
+    }
+
+    #[test]
+    fn infer_name_from_the_key_below() {
+        let chart = parse_chart(
+            "image:\n  ## @param - Image tag\n  tag: 1.0\n",
+            &Config::default(),
+        );
+
+        assert_eq!(chart.param_names(), vec!["image.tag".to_string()]);
+        assert!(chart.rules().is_empty());
+    }
+
+    #[test]
+    fn infer_name_only_from_the_next_line() {
+        let chart = parse_chart(
+            "hosts:\n  ## @param - Orphan\n  - example.com\n",
+            &Config::default(),
+        );
+
+        assert_eq!(chart.param_names(), vec![INFERRED_NAME]);
+        assert_eq!(chart.rules(), vec![Rule::UnresolvedName]);
+    }
+
+    #[test]
+    fn stacked_inferred_params_resolve_to_the_same_key() {
+        let chart = parse_chart(
+            "## @param - Replicas\n## @param - Replica count\nreplicas: 1\n",
+            &Config::default(),
+        );
+
+        assert_eq!(chart.param_names(), vec!["replicas", "replicas"]);
+        assert_eq!(chart.rules(), vec![Rule::DuplicateParam]);
+    }
+
+    fn modifiers(mod_str: &str) -> Vec<Modifier> {
+        let parser = MetadataParser::new(&Config::default());
+        let span = Span::new("values.yaml", 1, 1, 1, 1);
+
+        parser.parse_modifiers("param", Some(mod_str), &span, &Diagnostics::new())
+    }
+
+    #[test]
+    fn split_modifiers_balances_brackets() {
+        assert_eq!(
+            split_modifiers("[pattern: ^v[0-9]+$] Version"),
+            Some((Some("pattern: ^v[0-9]+$"), "Version"))
+        );
+        assert_eq!(
+            split_modifiers(r"[pattern: ^[^\]]+$] Anything"),
+            Some((Some(r"pattern: ^[^\]]+$"), "Anything"))
+        );
+        assert_eq!(
+            split_modifiers("Plain description"),
+            Some((None, "Plain description"))
+        );
+        assert_eq!(split_modifiers("[string Broken"), None);
+    }
+
+    #[test]
+    fn pattern_keeps_commas() {
+        assert_eq!(
+            modifiers("string, pattern: ^[a-z]{1,3}(,[a-z]+)*$"),
+            vec![
+                Modifier::String,
+                Modifier::Pattern("^[a-z]{1,3}(,[a-z]+)*$".to_string())
+            ]
+        );
+        assert_eq!(
+            modifiers(r"pattern: ^v\d+, nullable"),
+            vec![Modifier::Pattern(r"^v\d+".to_string()), Modifier::Nullable]
+        );
+        assert_eq!(
+            modifiers("pattern: ^a,b$, string"),
+            vec![Modifier::Pattern("^a,b$".to_string()), Modifier::String]
+        );
+        assert_eq!(
+            modifiers("enum: a|b, nullable"),
+            vec![
+                Modifier::Enum(vec!["a".to_string(), "b".to_string()]),
+                Modifier::Nullable
+            ]
+        );
+    }
+}
@@ -3,6 +3,7 @@
 * SPDX-License-Identifier: Apache-2.0
 */
 use crate::metadata::value::ValueMetadata;
+use crate::span::Span;
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -10,15 +11,19 @@ use std::rc::Rc;
 #[derive(Debug)]
 pub struct SectionMetadata {
     name: String,
+    span: Span,
     descr: RefCell<Vec<String>>,
+    descr_span: RefCell<Option<Span>>,
     chart_values: RefCell<Vec<Rc<ValueMetadata>>>,
 }
 
 impl SectionMetadata {
-    pub fn new(name: String) -> SectionMetadata {
+    pub fn new(name: String, span: Span) -> SectionMetadata {
         SectionMetadata {
             name,
+            span,
             descr: RefCell::new(Vec::new()),
+            descr_span: RefCell::new(None),
             chart_values: RefCell::new(Vec::new()),
         }
     }
@@ -30,4 +35,43 @@ impl SectionMetadata {
     pub fn add_descr(&self, line: String) {
         self.descr.borrow_mut().push(line);
     }
+
+    /// Extend the description block span to the given line (the block starts with the first extended line)
+    pub fn extend_descr_span(&self, span: Span) {
+        let mut descr_span = self.descr_span.borrow_mut();
+
+        *descr_span = match descr_span.as_ref() {
+            Some(block_span) => Some(block_span.to(&span)),
+            None => Some(span),
+        };
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The position of the section comment in the values file
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// The position of the description block (from its start tag to its end tag) in the values file
+    pub fn descr_span(&self) -> Option<Span> {
+        self.descr_span.borrow().clone()
+    }
+
+    pub fn descr(&self) -> Vec<String> {
+        self.descr.borrow().clone()
+    }
+
+    pub fn values(&self) -> Vec<Rc<ValueMetadata>> {
+        self.chart_values.borrow().clone()
+    }
+
+    pub fn has_value(&self, chart_value: &Rc<ValueMetadata>) -> bool {
+        self.chart_values
+            .borrow()
+            .iter()
+            .any(|value| Rc::ptr_eq(value, chart_value))
+    }
 }
@@ -3,61 +3,224 @@
 * SPDX-License-Identifier: Apache-2.0
 */
 
+use crate::helm::path::ValuePath;
+use crate::helm::values;
+use crate::metadata::modifier::Modifier;
 use crate::metadata::section::SectionMetadata;
+use crate::span::Span;
+use serde_yaml::Value;
+use std::cell::RefCell;
 use std::rc::Rc;
 
 // Param defines a chart values
 #[derive(Debug)]
+#[allow(dead_code)]
 pub struct ValueMetadata {
     name: String,
+    /// The name parsed once, as params are matched against every value path
+    path: ValuePath,
     param_type: Option<String>,
+    type_inferred: bool,
     value: Option<String>,
-    descr: Option<String>,
-    modifiers: Vec<String>,
+    descr: RefCell<Option<String>>,
+    descr_lines: RefCell<Vec<String>>,
+    modifiers: Vec<Modifier>,
+    span: Option<Span>,
+    descr_span: RefCell<Option<Span>>,
     section: Option<Rc<SectionMetadata>>,
     should_validate: bool,
     render_in_readme: bool,
     render_in_schema: bool,
+    subtree: bool,
+    required: bool,
+    inferred_name: bool,
 }
 
 impl ValueMetadata {
     pub(crate) fn new(
         name: String,
-        modifiers: Vec<String>,
+        modifiers: Vec<Modifier>,
         descr: Option<String>,
     ) -> ValueMetadata {
         ValueMetadata {
+            path: ValuePath::new(&name),
             name,
             param_type: None,
+            type_inferred: false,
             value: None,
             modifiers,
-            descr,
+            descr: RefCell::new(descr),
+            descr_lines: RefCell::new(Vec::new()),
+            span: None,
+            descr_span: RefCell::new(None),
             section: None,
             should_validate: true,
             render_in_readme: true,
             render_in_schema: true,
+            subtree: false,
+            required: false,
+            inferred_name: false,
         }
     }
 
+    #[allow(dead_code)]
     pub fn set_section(&mut self, section: Rc<SectionMetadata>) {
         self.section = Some(section);
     }
 
+    pub fn set_span(&mut self, span: Span) {
+        self.span = Some(span);
+    }
+
+    /// The position of the metadata comment in the values file
+    pub fn span(&self) -> Option<&Span> {
+        self.span.as_ref()
+    }
+
+    /// Extend the span of the description continuation lines to the given line
+    pub fn extend_descr_span(&self, span: Span) {
+        let mut descr_span = self.descr_span.borrow_mut();
+
+        *descr_span = match descr_span.as_ref() {
+            Some(block_span) => Some(block_span.to(&span)),
+            None => Some(span),
+        };
+    }
+
+    /// The position of all metadata comment lines (including description continuation lines)
+    pub fn comment_span(&self) -> Option<Span> {
+        let span = self.span.as_ref()?;
+
+        match self.descr_span.borrow().as_ref() {
+            Some(descr_span) => Some(span.to(descr_span)),
+            None => Some(span.clone()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn path(&self) -> &ValuePath {
+        &self.path
+    }
+
+    /// The whole description with continuation lines joined
+    pub fn descr(&self) -> Option<String> {
+        let descr = self.descr.borrow();
+        let descr_lines = self.descr_lines.borrow();
+
+        if descr_lines.is_empty() {
+            return descr.clone();
+        }
+
+        let mut parts: Vec<&str> = descr
+            .as_deref()
+            .filter(|text| !text.is_empty())
+            .into_iter()
+            .collect();
+        parts.extend(descr_lines.iter().map(|line| line.as_str()));
+
+        Some(parts.join(" "))
+    }
+
+    /// The part of the description that is placed on the param line
+    pub fn descr_first_line(&self) -> Option<String> {
+        self.descr.borrow().clone()
+    }
+
+    /// The description continuation lines
+    pub fn descr_continuation(&self) -> Vec<String> {
+        self.descr_lines.borrow().clone()
+    }
+
+    /// Append a continuation line to the description
+    pub fn append_descr(&self, line: &str) {
+        self.descr_lines.borrow_mut().push(line.to_string());
+    }
+
+    pub fn set_param_type(&mut self, param_type: String) {
+        self.param_type = Some(param_type);
+    }
+
+    /// Take the type from the default value unless it's declared in the metadata
+    pub fn infer_param_type(&mut self, value: &Value) {
+        if self.param_type.is_none() {
+            self.param_type = Some(values::value_type(value).to_string());
+            self.type_inferred = true;
+        }
+    }
+
+    /// The declared type or the one of the default value
+    pub fn param_type(&self) -> Option<&str> {
+        self.param_type.as_deref()
+    }
+
+    /// The type declared in the metadata (e.g. with the [string] modifier)
+    pub fn declared_type(&self) -> Option<&str> {
+        self.param_type.as_deref().filter(|_| !self.type_inferred)
+    }
+
+    /// Override the default value shown in the documentation
+    pub fn set_default(&mut self, value: String) {
+        self.value = Some(value);
+    }
+
+    pub fn default(&self) -> Option<&str> {
+        self.value.as_deref()
+    }
+
+    pub fn modifiers(&self) -> &[Modifier] {
+        &self.modifiers
+    }
+
     pub fn skip(&mut self) {
         self.should_validate = false;
         self.render_in_readme = false;
+        self.render_in_schema = false;
+    }
+
+    pub fn render_in_schema(&self) -> bool {
+        self.render_in_schema
     }
 
     pub fn has_skipped(&self) -> bool {
         !self.should_validate && !self.render_in_readme
     }
 
+    /// Document the whole subtree of the value (e.g. resources.*) with one param
+    pub fn set_subtree(&mut self) {
+        self.subtree = true;
+    }
+
+    pub fn is_subtree(&self) -> bool {
+        self.subtree
+    }
+
+    /// The value must be provided by the chart user (e.g. auth.existingSecret)
+    pub fn set_required(&mut self) {
+        self.required = true;
+    }
+
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+
+    /// The name is taken from the key that follows the metadata (e.g. ## @param - Image tag)
+    pub fn set_inferred_name(&mut self) {
+        self.inferred_name = true;
+    }
+
+    pub fn has_inferred_name(&self) -> bool {
+        self.inferred_name
+    }
+
     pub fn set_extra(&mut self) {
         self.should_validate = false;
         self.render_in_readme = true;
     }
 
-    pub fn has_extra(&mut self) -> bool {
+    pub fn has_extra(&self) -> bool {
         !self.should_validate && self.render_in_readme
     }
 }
@@ -0,0 +1,254 @@
+/*
+* Copyright 2024, Roma Hlushko
+* SPDX-License-Identifier: Apache-2.0
+*/
+
+use crate::config::{CommentDialect, Config, ModifiersConfig, TagsConfig};
+use crate::metadata::modifier::Modifier;
+use crate::metadata::value::ValueMetadata;
+
+/// ParamKind tells what tag documents the value
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamKind {
+    Param,
+    Skip,
+    Extra,
+}
+
+/// ParamComment is the dialect-independent content of the param metadata
+#[derive(Debug, Clone)]
+pub struct ParamComment {
+    pub kind: ParamKind,
+    pub name: String,
+    pub modifiers: Vec<Modifier>,
+    pub descr: String,
+    /// Description lines that follow the param line
+    pub continuation: Vec<String>,
+    /// The section the param belongs to (for dialects that define sections per param)
+    pub section: Option<String>,
+    /// Whether the name can be inferred from the key that goes right after the comment
+    pub inferred: bool,
+}
+
+impl ParamComment {
+    /// Collect the param metadata in the dialect-independent form (description lines are kept as they are).
+    /// The section is not set and the name is not considered inferred
+    pub fn from_metadata(param: &ValueMetadata) -> ParamComment {
+        let mut modifiers: Vec<Modifier> = param.modifiers().to_vec();
+
+        if let Some(param_type) = param.declared_type() {
+            if !modifiers.iter().any(|m| m.param_type().is_some()) {
+                modifiers.push(Modifier::from_param_type(param_type));
+            }
+        }
+
+        if param.is_subtree() && !modifiers.contains(&Modifier::Subtree) {
+            modifiers.push(Modifier::Subtree);
+        }
+
+        if param.is_required() && !modifiers.contains(&Modifier::Required) {
+            modifiers.push(Modifier::Required);
+        }
+
+        if let Some(default) = param.default() {
+            if !modifiers.iter().any(|m| matches!(m, Modifier::Default(_))) {
+                modifiers.push(Modifier::Default(default.to_string()));
+            }
+        }
+
+        ParamComment {
+            kind: if param.has_skipped() {
+                ParamKind::Skip
+            } else if param.has_extra() {
+                ParamKind::Extra
+            } else {
+                ParamKind::Param
+            },
+            name: param.name().to_string(),
+            modifiers,
+            descr: param.descr_first_line().unwrap_or_default(),
+            continuation: param.descr_continuation(),
+            section: None,
+            inferred: false,
+        }
+    }
+}
+
+/// MetadataWriter formats metadata comments in the configured dialect
+pub struct MetadataWriter {
+    dialect: CommentDialect,
+    format: String,
+    tags: TagsConfig,
+    modifiers: ModifiersConfig,
+}
+
+impl MetadataWriter {
+    pub fn new(config: &Config) -> MetadataWriter {
+        MetadataWriter {
+            dialect: config.comments.dialect.clone(),
+            format: config.comments.format.clone(),
+            tags: config.tags.clone(),
+            modifiers: config.modifiers.clone(),
+        }
+    }
+
+    pub fn dialect(&self) -> &CommentDialect {
+        &self.dialect
+    }
+
+    /// Format the param comment lines.
+    /// Returns None if the param can't be expressed in the dialect
+    pub fn param(&self, indent: &str, param: &ParamComment) -> Option<Vec<String>> {
+        match self.dialect {
+            CommentDialect::Bitnami => Some(self.bitnami_param(indent, param)),
+            CommentDialect::HelmDocs => self.helm_docs_param(indent, param),
+        }
+    }
+
+    /// Modifiers of the param that are lost when it's written in the dialect
+    pub fn unsupported_modifiers(&self, param: &ParamComment) -> Vec<Modifier> {
+        match self.dialect {
+            CommentDialect::Bitnami => vec![],
+            CommentDialect::HelmDocs => param
+                .modifiers
+                .iter()
+                .filter(|m| m.param_type().is_none() && !matches!(m, Modifier::Default(_)))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Format the section header lines.
+    /// Dialects that define sections per param have no section headers
+    pub fn section(&self, indent: &str, name: &str) -> Vec<String> {
+        match self.dialect {
+            CommentDialect::Bitnami => {
+                vec![self.comment(indent, &format!("{} {}", self.tags.section, name))]
+            }
+            CommentDialect::HelmDocs => vec![],
+        }
+    }
+
+    /// Format the section description block.
+    /// Dialects with no section descriptions keep it as plain comments
+    pub fn section_descr(&self, indent: &str, descr: &[String]) -> Vec<String> {
+        match self.dialect {
+            CommentDialect::Bitnami => {
+                let mut lines = vec![self.comment(indent, &self.tags.description_start)];
+
+                lines.extend(descr.iter().map(|line| self.comment(indent, line)));
+                lines.push(self.comment(indent, &self.tags.description_end));
+
+                lines
+            }
+            CommentDialect::HelmDocs => descr
+                .iter()
+                .map(|line| format!("{}# {}", indent, line).trim_end().to_string())
+                .collect(),
+        }
+    }
+
+    fn comment(&self, indent: &str, text: &str) -> String {
+        format!("{}{} {}", indent, self.format, text)
+            .trim_end()
+            .to_string()
+    }
+
+    fn bitnami_param(&self, indent: &str, param: &ParamComment) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        if let Some(section) = &param.section {
+            lines.extend(self.section(indent, section));
+        }
+
+        let tag = match param.kind {
+            ParamKind::Param => &self.tags.param,
+            ParamKind::Skip => &self.tags.skip,
+            ParamKind::Extra => &self.tags.extra,
+        };
+
+        let mut line = format!("{} {}", tag, param.name);
+
+        if param.kind != ParamKind::Skip && !param.modifiers.is_empty() {
+            let modifiers: Vec<String> = param
+                .modifiers
+                .iter()
+                .map(|m| m.format(&self.modifiers))
+                .collect();
+
+            line.push_str(&format!(" [{}]", modifiers.join(", ")));
+        }
+
+        if !param.descr.is_empty() {
+            line.push_str(&format!(" {}", param.descr));
+        }
+
+        lines.push(self.comment(indent, &line));
+
+        if param.kind != ParamKind::Skip {
+            lines.extend(
+                param
+                    .continuation
+                    .iter()
+                    .map(|descr_line| self.comment(indent, descr_line)),
+            );
+        }
+
+        lines
+    }
+
+    fn helm_docs_param(&self, indent: &str, param: &ParamComment) -> Option<Vec<String>> {
+        let mut lines = Vec::new();
+
+        match param.kind {
+            // helm-docs annotations refer to values in the file only
+            ParamKind::Extra => return None,
+            ParamKind::Skip if !param.inferred => return None,
+            ParamKind::Skip => {
+                lines.push(format!("{}# @ignored", indent));
+
+                return Some(lines);
+            }
+            ParamKind::Param => {}
+        }
+
+        let descr = match param.modifiers.iter().find_map(helm_docs_type) {
+            Some(param_type) => format!("({}) {}", param_type, param.descr),
+            None => param.descr.clone(),
+        };
+
+        let line = if param.inferred {
+            format!("{}# -- {}", indent, descr)
+        } else {
+            format!("{}# {} -- {}", indent, param.name, descr)
+        };
+
+        lines.push(line.trim_end().to_string());
+        lines.extend(
+            param
+                .continuation
+                .iter()
+                .map(|descr_line| format!("{}# {}", indent, descr_line)),
+        );
+
+        for modifier in &param.modifiers {
+            if let Modifier::Default(value) = modifier {
+                lines.push(format!("{}# @default -- {}", indent, value));
+            }
+        }
+
+        if let Some(section) = &param.section {
+            lines.push(format!("{}# @section -- {}", indent, section));
+        }
+
+        Some(lines)
+    }
+}
+
+/// The helm-docs name of the type the modifier declares (e.g. object for [object])
+fn helm_docs_type(modifier: &Modifier) -> Option<&'static str> {
+    match modifier {
+        Modifier::Object => Some("object"),
+        modifier => modifier.param_type(),
+    }
+}
@@ -0,0 +1,216 @@
+/*
+* Copyright 2024, Roma Hlushko
+* SPDX-License-Identifier: Apache-2.0
+*/
+
+use crate::config::{CommentDialect, Config, ModifiersConfig};
+use crate::diagnostics::{Diagnostic, Diagnostics, Rule};
+use crate::helm::document::ValuesDocument;
+use crate::metadata::chart::ChartMetadata;
+use crate::metadata::writer::{MetadataWriter, ParamComment};
+use crate::span::Span;
+
+/// Migrator rewrites metadata comments from one dialect (or tag names) to another
+pub struct Migrator {
+    from_dialect: CommentDialect,
+    from_modifiers: ModifiersConfig,
+    writer: MetadataWriter,
+}
+
+impl Migrator {
+    pub fn new(from: &Config, to: &Config) -> Migrator {
+        Migrator {
+            from_dialect: from.comments.dialect.clone(),
+            from_modifiers: from.modifiers.clone(),
+            writer: MetadataWriter::new(to),
+        }
+    }
+
+    /// Rewrite metadata comments of the values document in the target dialect.
+    /// Everything else in the document is kept as is
+    pub fn migrate(
+        &self,
+        document: &mut ValuesDocument,
+        metadata: &ChartMetadata,
+        diagnostics: &Diagnostics,
+    ) {
+        // helm-docs sections are part of the param annotations
+        if self.from_dialect == CommentDialect::Bitnami {
+            for section in metadata.sections() {
+                let span = section.span();
+                let indent = document.indent(span.start_line).to_string();
+
+                document.replace(
+                    span.start_line,
+                    span.end_line,
+                    self.writer.section(&indent, section.name()),
+                );
+
+                if let Some(descr_span) = section.descr_span() {
+                    document.replace(
+                        descr_span.start_line,
+                        descr_span.end_line,
+                        self.writer.section_descr(&indent, &section.descr()),
+                    );
+                }
+            }
+        }
+
+        let mut curr_section: Option<String> = None;
+
+        for param in metadata.values() {
+            let Some(span) = param.comment_span() else {
+                continue;
+            };
+
+            let section = metadata
+                .sections()
+                .iter()
+                .find(|section| section.has_value(param))
+                .map(|section| section.name().to_string());
+
+            let section = match (&self.from_dialect, self.writer.dialect()) {
+                (_, CommentDialect::HelmDocs) => section,
+                // section headers are already in place
+                (CommentDialect::Bitnami, CommentDialect::Bitnami) => None,
+                // a section header is needed where params of another section start
+                (CommentDialect::HelmDocs, CommentDialect::Bitnami) => {
+                    if section == curr_section {
+                        None
+                    } else {
+                        if section.is_none() {
+                            diagnostics.report(self.lossy(
+                                &span,
+                                format!(
+                                    "The {} param has no section, but follows params of the {} section",
+                                    param.name(),
+                                    curr_section.as_deref().unwrap_or_default()
+                                ),
+                            ));
+                        }
+
+                        curr_section.clone_from(&section);
+
+                        section
+                    }
+                }
+            };
+
+            let comment = ParamComment {
+                section,
+                inferred: document
+                    .next_content_line(span.end_line)
+                    .and_then(|line| document.line(line))
+                    .is_some_and(|line| line.key_path() == Some(param.name())),
+                ..ParamComment::from_metadata(param)
+            };
+
+            let unsupported = self.writer.unsupported_modifiers(&comment);
+
+            if !unsupported.is_empty() {
+                let modifiers: Vec<String> = unsupported
+                    .iter()
+                    .map(|m| m.format(&self.from_modifiers))
+                    .collect();
+
+                diagnostics.report(self.lossy(
+                    &span,
+                    format!(
+                        "The {} modifiers of the {} param are not supported in the target dialect",
+                        modifiers.join(", "),
+                        param.name()
+                    ),
+                ));
+            }
+
+            let indent = document.indent(span.start_line).to_string();
+
+            match self.writer.param(&indent, &comment) {
+                Some(new_lines) => document.replace_param(param, new_lines),
+                None => diagnostics.report(self.lossy(
+                    &span,
+                    format!(
+                        "The {} param can't be expressed in the target dialect, so it's kept as is",
+                        param.name()
+                    ),
+                )),
+            }
+        }
+    }
+
+    fn lossy(&self, span: &Span, message: String) -> Diagnostic {
+        Diagnostic::warning(Rule::LossyMigration, &span.file, message).with_span(span)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::parse_chart;
+
+    const BITNAMI_VALUES: &str = "\
+## @section Common parameters
+##
+
+## @param kubeVersion Override Kubernetes version
+##
+kubeVersion: \"\"
+## @param nameOverride String to partially override common.names.name
+## that spans two lines
+nameOverride: \"\"
+## @param tolerations [array] Tolerations for pod assignment
+tolerations: []
+## @skip image.digest
+image:
+  digest: \"\"
+";
+
+    fn config(dialect: CommentDialect) -> Config {
+        let mut config = Config::default();
+        config.comments.dialect = dialect;
+
+        config
+    }
+
+    /// Params as (name, description) pairs
+    fn params(content: &str, config: &Config) -> Vec<(String, Option<String>)> {
+        parse_chart(content, config)
+            .metadata
+            .values()
+            .iter()
+            .map(|param| (param.name().to_string(), param.descr()))
+            .collect()
+    }
+
+    fn migrate(content: &str, from: &Config, to: &Config) -> String {
+        let mut chart = parse_chart(content, from);
+
+        Migrator::new(from, to).migrate(&mut chart.document, &chart.metadata, &chart.diagnostics);
+
+        chart.document.render()
+    }
+
+    #[test]
+    fn round_trip_keeps_params() {
+        let bitnami = config(CommentDialect::Bitnami);
+        let helm_docs = config(CommentDialect::HelmDocs);
+
+        let expected = params(BITNAMI_VALUES, &bitnami);
+
+        let migrated = migrate(BITNAMI_VALUES, &bitnami, &helm_docs);
+        // the skip of a nested key can't be expressed in helm-docs, so it's kept in the bitnami form
+        assert_eq!(params(&migrated, &helm_docs), expected[..3]);
+
+        let migrated = migrate(&migrated, &helm_docs, &bitnami);
+        assert_eq!(params(&migrated, &bitnami), expected);
+
+        assert!(migrated.contains("## @param nameOverride String to partially override common.names.name\n## that spans two lines\n"));
+    }
+
+    #[test]
+    fn migration_is_idempotent() {
+        let bitnami = config(CommentDialect::Bitnami);
+
+        assert_eq!(migrate(BITNAMI_VALUES, &bitnami, &bitnami), BITNAMI_VALUES);
+    }
+}
@@ -0,0 +1,292 @@
+/*
+* Copyright 2024, Roma Hlushko
+* SPDX-License-Identifier: Apache-2.0
+*/
+
+use crate::helm::path::{self, Segment};
+use crate::helm::values::ChartValues;
+use crate::metadata::chart::ChartMetadata;
+use crate::metadata::modifier::Modifier;
+use crate::metadata::value::ValueMetadata;
+use anyhow::Result;
+use serde_json::{json, Map, Value as JsonValue};
+use serde_yaml::Value;
+use std::fs;
+use std::path::Path;
+
+const SCHEMA_DRAFT: &str = "http://json-schema.org/draft-07/schema#";
+
+/// SchemaRenderer generates JSON Schema (values.schema.json) out of the values metadata
+#[derive(Default)]
+pub struct SchemaRenderer {}
+
+impl SchemaRenderer {
+    pub fn new() -> SchemaRenderer {
+        SchemaRenderer {}
+    }
+
+    /// Write the values schema to the given file (e.g. values.schema.json)
+    pub fn render<P: AsRef<Path>>(
+        &self,
+        schema_path: P,
+        metadata: &ChartMetadata,
+        values: &ChartValues,
+    ) -> Result<()> {
+        let schema = self.build_schema(metadata, values);
+
+        log::debug!(
+            "Writing values schema: {:?}",
+            schema_path.as_ref().to_string_lossy()
+        );
+
+        fs::write(
+            schema_path,
+            format!("{}\n", serde_json::to_string_pretty(&schema)?),
+        )?;
+
+        Ok(())
+    }
+
+    fn build_schema(&self, metadata: &ChartMetadata, values: &ChartValues) -> JsonValue {
+        let mut root = self.object_schema();
+
+        for param in metadata.values() {
+            if !param.render_in_schema() {
+                continue;
+            }
+
+            let Ok(segments) = path::parse(param.name()) else {
+                continue;
+            };
+
+            // the schema can't describe a single list item without constraining the others
+            if segments.iter().any(|s| matches!(s, Segment::Index(_))) {
+                log::debug!("Skipping {} as it refers to a list item", param.name());
+
+                continue;
+            }
+
+            let value = if param.is_subtree() {
+                values.subtree(param.name())
+            } else {
+                values.get(param.name())
+            };
+            let mut node = &mut root;
+
+            // walk down to the param's parent creating intermediate objects and lists along the way
+            for (idx, segment) in segments.iter().enumerate() {
+                let next = segments.get(idx + 1);
+
+                if let (Segment::Key(key), None) = (segment, next) {
+                    if param.is_required() {
+                        add_required(node, key);
+                    }
+                }
+
+                let (children, key) = match segment {
+                    Segment::Key(key) => (
+                        node.as_object_mut()
+                            .unwrap()
+                            .entry("properties")
+                            .or_insert_with(|| JsonValue::Object(Map::new()))
+                            .as_object_mut()
+                            .unwrap(),
+                        key.as_str(),
+                    ),
+                    // list items are described by the items keyword
+                    Segment::Wildcard => (node.as_object_mut().unwrap(), "items"),
+                    Segment::Index(_) => unreachable!(),
+                };
+
+                let Some(next) = next else {
+                    let param_schema = self.param_schema(param, value.as_ref());
+
+                    match children.get_mut(key).and_then(|n| n.as_object_mut()) {
+                        // the param was already referenced as a parent of another param
+                        Some(existing) => existing.extend(param_schema),
+                        None => {
+                            children.insert(key.to_string(), JsonValue::Object(param_schema));
+                        }
+                    }
+
+                    break;
+                };
+
+                node = children.entry(key).or_insert_with(|| match next {
+                    Segment::Wildcard => self.array_schema(),
+                    _ => self.object_schema(),
+                });
+            }
+        }
+
+        if let Some(root) = root.as_object_mut() {
+            root.insert("$schema".to_string(), json!(SCHEMA_DRAFT));
+        }
+
+        root
+    }
+
+    fn array_schema(&self) -> JsonValue {
+        json!({
+            "type": "array",
+        })
+    }
+
+    fn object_schema(&self) -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {},
+        })
+    }
+
+    fn param_schema(&self, param: &ValueMetadata, value: Option<&Value>) -> Map<String, JsonValue> {
+        let mut schema = Map::new();
+
+        // the null default has to be valid against the declared type too
+        let nullable =
+            param.modifiers().contains(&Modifier::Nullable) || matches!(value, Some(Value::Null));
+
+        // the declared type takes precedence over the one of the default value
+        let param_type = param
+            .param_type()
+            .and_then(schema_type)
+            .or_else(|| value.and_then(infer_type));
+
+        match param_type {
+            Some(param_type) if nullable => {
+                schema.insert("type".to_string(), json!([param_type, "null"]));
+            }
+            Some(param_type) => {
+                schema.insert("type".to_string(), json!(param_type));
+            }
+            None => {}
+        }
+
+        for modifier in param.modifiers() {
+            match modifier {
+                Modifier::Enum(values) => {
+                    let values: Vec<JsonValue> = values
+                        .iter()
+                        .map(|value| enum_value(value, param_type))
+                        .collect();
+
+                    schema.insert("enum".to_string(), json!(values));
+                }
+                Modifier::Min(min) => {
+                    schema.insert("minimum".to_string(), json_number(*min));
+                }
+                Modifier::Max(max) => {
+                    schema.insert("maximum".to_string(), json_number(*max));
+                }
+                Modifier::Pattern(pattern) => {
+                    schema.insert("pattern".to_string(), json!(pattern));
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(descr) = param.descr().filter(|descr| !descr.is_empty()) {
+            schema.insert("description".to_string(), json!(descr));
+        }
+
+        if let Some(value) = value {
+            if let Ok(default) = serde_json::to_value(value) {
+                schema.insert("default".to_string(), default);
+            }
+        }
+
+        schema
+    }
+}
+
+/// List the key in the required keywords of the object schema
+fn add_required(node: &mut JsonValue, key: &str) {
+    let Some(required) = node.as_object_mut().and_then(|node| {
+        node.entry("required")
+            .or_insert_with(|| json!([]))
+            .as_array_mut()
+    }) else {
+        return;
+    };
+
+    if !required.iter().any(|required_key| required_key == key) {
+        required.push(json!(key));
+    }
+}
+
+/// Allowed values are strings for string params and YAML scalars otherwise (e.g. 1 or true)
+fn enum_value(value: &str, param_type: Option<&str>) -> JsonValue {
+    if param_type == Some("string") {
+        return json!(value);
+    }
+
+    serde_yaml::from_str::<Value>(value)
+        .ok()
+        .and_then(|value| serde_json::to_value(value).ok())
+        .filter(|value| !value.is_object() && !value.is_array())
+        .unwrap_or_else(|| json!(value))
+}
+
+/// Whole numbers are written as integers (e.g. 1 rather than 1.0)
+fn json_number(number: f64) -> JsonValue {
+    if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+        json!(number as i64)
+    } else {
+        json!(number)
+    }
+}
+
+/// Map the declared value type (e.g. list) to JSON Schema type
+fn schema_type(param_type: &str) -> Option<&'static str> {
+    match param_type {
+        "list" => Some("array"),
+        "map" | "object" | "dict" => Some("object"),
+        "string" => Some("string"),
+        "int" => Some("integer"),
+        "float" => Some("number"),
+        "bool" => Some("boolean"),
+        _ => None,
+    }
+}
+
+/// Infer JSON Schema type from the default YAML value
+fn infer_type(value: &Value) -> Option<&'static str> {
+    match value {
+        Value::Null => None,
+        Value::Bool(_) => Some("boolean"),
+        Value::Number(number) if number.is_f64() => Some("number"),
+        Value::Number(_) => Some("integer"),
+        Value::String(_) => Some("string"),
+        Value::Sequence(_) => Some("array"),
+        Value::Mapping(_) => Some("object"),
+        Value::Tagged(tagged) => infer_type(&tagged.value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema_type_of(modifiers: Vec<Modifier>, param_type: &str, value: &Value) -> JsonValue {
+        let mut param = ValueMetadata::new("param".to_string(), modifiers, None);
+        param.set_param_type(param_type.to_string());
+
+        SchemaRenderer::new().param_schema(&param, Some(value))["type"].clone()
+    }
+
+    #[test]
+    fn null_default_allows_null() {
+        assert_eq!(
+            schema_type_of(vec![], "string", &Value::Null),
+            json!(["string", "null"])
+        );
+        assert_eq!(
+            schema_type_of(vec![Modifier::Nullable], "int", &1.into()),
+            json!(["integer", "null"])
+        );
+        assert_eq!(
+            schema_type_of(vec![], "string", &"web".into()),
+            json!("string")
+        );
+    }
+}
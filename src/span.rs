@@ -0,0 +1,64 @@
+/*
+* Copyright 2024, Roma Hlushko
+* SPDX-License-Identifier: Apache-2.0
+*/
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Span points to a range of text in a source file.
+/// Lines and columns are 1-based, the end column is exclusive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Span {
+    pub file: PathBuf,
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl Span {
+    pub fn new<P: AsRef<Path>>(
+        file: P,
+        start_line: usize,
+        start_column: usize,
+        end_line: usize,
+        end_column: usize,
+    ) -> Span {
+        Span {
+            file: file.as_ref().to_path_buf(),
+            start_line,
+            start_column,
+            end_line,
+            end_column,
+        }
+    }
+
+    /// Span of the text within one line
+    pub fn line<P: AsRef<Path>>(
+        file: P,
+        line: usize,
+        start_column: usize,
+        end_column: usize,
+    ) -> Span {
+        Span::new(file, line, start_column, line, end_column)
+    }
+
+    /// Span of the trimmed content of the given line
+    pub fn trimmed_line<P: AsRef<Path>>(file: P, line: usize, content: &str) -> Span {
+        let start_column = content.chars().take_while(|c| c.is_whitespace()).count() + 1;
+        let end_column = content.trim_end().chars().count() + 1;
+
+        Span::line(file, line, start_column, end_column.max(start_column))
+    }
+
+    /// Span that starts where this one starts and ends where the other one ends
+    pub fn to(&self, other: &Span) -> Span {
+        Span::new(
+            &self.file,
+            self.start_line,
+            self.start_column,
+            other.end_line,
+            other.end_column,
+        )
+    }
+}
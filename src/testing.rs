@@ -0,0 +1,55 @@
+/*
+* Copyright 2024, Roma Hlushko
+* SPDX-License-Identifier: Apache-2.0
+*/
+//! Shared fixtures for tests that parse values files in memory
+
+use crate::config::Config;
+use crate::diagnostics::{Diagnostics, Rule};
+use crate::helm::document::ValuesDocument;
+use crate::helm::parser::ValuesParser;
+use crate::helm::values::ChartValues;
+use crate::metadata::chart::ChartMetadata;
+use crate::metadata::parser::MetadataParser;
+
+/// The values file parsed the same way the commands do it
+pub struct ParsedChart {
+    pub document: ValuesDocument,
+    pub values: ChartValues,
+    pub metadata: ChartMetadata,
+    pub diagnostics: Diagnostics,
+}
+
+impl ParsedChart {
+    /// Rules of the reported diagnostics
+    pub fn rules(&self) -> Vec<Rule> {
+        self.diagnostics
+            .items()
+            .iter()
+            .map(|diagnostic| diagnostic.rule)
+            .collect()
+    }
+
+    /// Names of the parsed params in the order they are documented
+    pub fn param_names(&self) -> Vec<String> {
+        self.metadata
+            .values()
+            .iter()
+            .map(|param| param.name().to_string())
+            .collect()
+    }
+}
+
+pub fn parse_chart(content: &str, config: &Config) -> ParsedChart {
+    let diagnostics = Diagnostics::new();
+    let document = ValuesDocument::parse("values.yaml", content);
+    let values = ValuesParser::new().parse(&document, &diagnostics).unwrap();
+    let metadata = MetadataParser::new(config).parse(&document, &values, &diagnostics);
+
+    ParsedChart {
+        document,
+        values,
+        metadata,
+        diagnostics,
+    }
+}